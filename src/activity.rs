@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Settings, encryption, logging, project::Project, tracker::TimeEntry};
+
+/// days of activity kept, older days' files are removed as each new day's
+/// is started
+const KEEP_DAYS: i64 = 90;
+
+/// what `watch` noticed activity through
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Files,
+    Shell,
+    App,
+}
+
+/// the harvest timer when activity was noticed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerState {
+    /// running for the project with the activity
+    Running,
+
+    /// running for another project
+    Other,
+
+    Stopped,
+
+    /// harvest couldn't be asked
+    Unknown,
+}
+
+impl TimerState {
+    pub fn of(running: Option<&TimeEntry>, project: &Project) -> Self {
+        match running.map(|t| t.project.id) {
+            Some(id) if id == project.harvest_project_id => TimerState::Running,
+            Some(_) => TimerState::Other,
+            None => TimerState::Stopped,
+        }
+    }
+}
+
+/// a line of the activity log, one per project each time `watch` sees
+/// activity in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Activity {
+    pub at: DateTime<Local>,
+    pub project: String,
+    pub harvest_project_id: i32,
+    pub source: Source,
+
+    /// a hash of the changed file or directory relative to the project root,
+    /// so files can be told apart without the log naming them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_hash: Option<String>,
+
+    pub timer: TimerState,
+}
+
+impl Activity {
+    pub fn new(project: &Project, source: Source, path: Option<&Path>, timer: TimerState) -> Self {
+        Activity {
+            at: Local::now(),
+            project: project.name.clone(),
+            harvest_project_id: project.harvest_project_id,
+            source,
+            path_hash: path.map(|path| hash_path(path.strip_prefix(&project.root).unwrap_or(path))),
+            timer,
+        }
+    }
+}
+
+/// the activity directory in the config directory, with a file per day
+fn log_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join("activity")
+}
+
+/// a day's activity log, like activity/2024-01-08.jsonl
+pub fn log_path(config_dir: &Path, day: NaiveDate) -> PathBuf {
+    log_dir(config_dir).join(format!("{}.jsonl", day.format("%Y-%m-%d")))
+}
+
+/// appends a line to the day's activity log, encrypted with `[encryption]`,
+/// warning when it can't be written
+pub fn record(config_dir: &Path, settings: &Settings, activity: &Activity) {
+    let day = settings.date(activity.at);
+    let path = log_path(config_dir, day);
+    if !path.exists() {
+        prune(config_dir, day);
+    }
+    let written = serde_json::to_string(activity)
+        .map_err(anyhow::Error::from)
+        .and_then(|line| encryption::append_line(&path, &line));
+    if let Err(e) = written {
+        logging::warn!("couldn't write to the activity log: {:#}", e);
+    }
+}
+
+/// removes the logs of days more than `KEEP_DAYS` before `today`
+fn prune(config_dir: &Path, today: NaiveDate) {
+    let Ok(entries) = fs::read_dir(log_dir(config_dir)) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let old = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok())
+            .is_some_and(|day| (today - day).num_days() > KEEP_DAYS);
+        if old {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// the activity logged on a day, in order, skipping lines that can't be read
+pub fn read(
+    config_dir: &Path,
+    settings: &Settings,
+    day: NaiveDate,
+) -> anyhow::Result<Vec<Activity>> {
+    let Some(contents) = encryption::read_to_string(&log_path(config_dir, day))? else {
+        return Ok(Vec::new());
+    };
+    let mut activity: Vec<Activity> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Activity>(line).ok())
+        .filter(|a| settings.date(a.at) == day)
+        .collect();
+    activity.sort_by_key(|a| a.at);
+    Ok(activity)
+}
+
+/// 64 bit FNV-1a, as hex, which unlike std's hasher stays the same between
+/// releases
+pub fn hash_path(path: &Path) -> String {
+    let hash = path
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,569 @@
+use std::{
+    collections::HashMap,
+    io::{self, IsTerminal},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use crate::{
+    rate_limit,
+    tracker::{
+        EntryUpdate, Invoice, Named, ProjectAssignment, TaskAssignment, TimeEntry, TimeTracker,
+        User,
+    },
+};
+
+const HARVEST_API_URL: &str = "https://api.harvestapp.com/v2";
+
+/// a client for harvest's v2 api, with the credentials of one account
+#[derive(Clone)]
+pub struct Harvest {
+    client: reqwest::Client,
+    token: String,
+    account_id: i32,
+}
+
+/// how long a running timer that was looked up is trusted, so a burst of
+/// checks makes one request
+const RUNNING_TTL: Duration = Duration::from_secs(10);
+
+/// a running timer and when it was looked up
+type Running = (Instant, Option<TimeEntry>);
+
+/// user ids for the life of the process, and recently looked up running
+/// timers, by credentials
+static USER_IDS: OnceLock<Mutex<HashMap<String, i32>>> = OnceLock::new();
+static RUNNING: OnceLock<Mutex<HashMap<String, Running>>> = OnceLock::new();
+
+/// drops the remembered running timer after changing entries
+fn forget_running_time_entry() {
+    if let Some(running) = RUNNING.get() {
+        running.lock().unwrap().clear();
+    }
+}
+
+#[derive(Deserialize)]
+struct TimeEntriesPage {
+    time_entries: Vec<TimeEntry>,
+    next_page: Option<u32>,
+    total_pages: u32,
+    total_entries: u32,
+}
+
+#[derive(Deserialize)]
+struct HarvestUser {
+    id: i32,
+    #[serde(default)]
+    first_name: String,
+    #[serde(default)]
+    last_name: String,
+    #[serde(default)]
+    email: String,
+}
+
+#[derive(Deserialize)]
+struct HarvestProjectAssignment {
+    #[serde(default = "active")]
+    is_active: bool,
+    project: Named,
+    client: Option<Named>,
+    #[serde(default)]
+    task_assignments: Vec<HarvestTaskAssignment>,
+}
+
+#[derive(Deserialize)]
+struct HarvestTaskAssignment {
+    #[serde(default = "active")]
+    is_active: bool,
+    #[serde(default)]
+    billable: bool,
+    task: Named,
+}
+
+fn active() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct ProjectAssignmentsPage {
+    project_assignments: Vec<HarvestProjectAssignment>,
+    next_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct UsersPage {
+    users: Vec<HarvestUser>,
+    next_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ClientsPage {
+    clients: Vec<Named>,
+    next_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct HarvestInvoice {
+    id: i64,
+    #[serde(default)]
+    number: String,
+}
+
+#[derive(Deserialize)]
+struct Company {
+    base_uri: String,
+    #[serde(default)]
+    wants_timestamp_timers: bool,
+}
+
+impl Harvest {
+    pub fn new(token: &str, account_id: i32) -> Harvest {
+        Harvest {
+            client: reqwest::Client::new(),
+            token: token.to_string(),
+            account_id,
+        }
+    }
+
+    /// the account and token, as a cache key
+    fn credentials_key(&self) -> String {
+        format!("{}:{}", self.account_id, self.token)
+    }
+
+    /// a request to a path under the api's url, with the credentials, after
+    /// waiting for the rate limit
+    async fn builder(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        rate_limit::acquire().await;
+        self.client
+            .request(method, format!("{}/{}", HARVEST_API_URL, path))
+            .bearer_auth(&self.token)
+            .header("Harvest-Account-Id", self.account_id.to_string())
+            .header("User-Agent", env!("CARGO_PKG_NAME"))
+    }
+
+    /// sends a request with a query and optionally a json body, and parses
+    /// the json response
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<serde_json::Value>,
+    ) -> anyhow::Result<T> {
+        let mut request = self.builder(method, path).await.query(query);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        self.request(Method::GET, path, query, None).await
+    }
+
+    /// every time entry matching `query`, following pagination and showing
+    /// progress on stderr when there's more than one page
+    async fn list_time_entries(&self, query: &[(&str, String)]) -> anyhow::Result<Vec<TimeEntry>> {
+        let mut entries = Vec::new();
+        let mut page = Some(1);
+        let show_progress = io::stderr().is_terminal();
+        let mut shown_progress = false;
+
+        while let Some(current_page) = page {
+            let mut page_query = query.to_vec();
+            page_query.push(("page", current_page.to_string()));
+            page_query.push(("per_page", "200".to_string()));
+            let response: TimeEntriesPage = self
+                .get("time_entries", &page_query)
+                .await
+                .context("failed to list time entries")?;
+
+            entries.extend(response.time_entries);
+            page = response.next_page;
+
+            if show_progress && response.total_pages > 1 {
+                eprint!(
+                    "\rfetched page {} of {} ({} entries)",
+                    current_page, response.total_pages, response.total_entries
+                );
+                shown_progress = true;
+            }
+        }
+
+        if shown_progress {
+            // clears the progress line so it doesn't mix with the output
+            eprint!("\r\x1b[K");
+        }
+
+        Ok(entries)
+    }
+
+    /// creates a time entry for a day, with hours for time already spent or
+    /// without to start a timer
+    async fn create_time_entry(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        spent_date: NaiveDate,
+        hours: Option<f32>,
+        notes: Option<String>,
+    ) -> anyhow::Result<TimeEntry> {
+        forget_running_time_entry();
+        let mut body = json!({
+            "project_id": project_id,
+            "task_id": task_id,
+            "spent_date": spent_date.to_string(),
+            "notes": notes,
+        });
+        if let Some(hours) = hours {
+            body["hours"] = json!(hours);
+        }
+
+        self.request(Method::POST, "time_entries", &[], Some(body))
+            .await
+    }
+
+    /// patches a time entry, or stops or restarts its timer with an `action`
+    async fn patch_time_entry(
+        &self,
+        entry: &TimeEntry,
+        action: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> anyhow::Result<TimeEntry> {
+        forget_running_time_entry();
+        let path = match action {
+            Some(action) => format!("time_entries/{}/{}", entry.id, action),
+            None => format!("time_entries/{}", entry.id),
+        };
+
+        self.request(Method::PATCH, &path, &[], body).await
+    }
+
+    async fn company(&self) -> anyhow::Result<Company> {
+        self.get("company", &[])
+            .await
+            .context("failed to retrieve the harvest company")
+    }
+}
+
+#[async_trait]
+impl TimeTracker for Harvest {
+    async fn current_user(&self) -> anyhow::Result<User> {
+        let me: HarvestUser = self
+            .get("users/me", &[])
+            .await
+            .context("failed to retrieve the current harvest user")?;
+        Ok(User {
+            id: me.id,
+            name: format!("{} {}", me.first_name, me.last_name),
+            email: me.email,
+        })
+    }
+
+    async fn current_user_id(&self) -> anyhow::Result<i32> {
+        let user_ids = USER_IDS.get_or_init(Default::default);
+        let key = self.credentials_key();
+        if let Some(id) = user_ids.lock().unwrap().get(&key) {
+            return Ok(*id);
+        }
+
+        let id = self.current_user().await?.id;
+        user_ids.lock().unwrap().insert(key, id);
+        Ok(id)
+    }
+
+    /// harvest allows at most one running timer. it's reused for a few
+    /// seconds unless clockhand changed an entry since
+    async fn running_time_entry(&self) -> anyhow::Result<Option<TimeEntry>> {
+        let running = RUNNING.get_or_init(Default::default);
+        let key = self.credentials_key();
+        if let Some((at, entry)) = running.lock().unwrap().get(&key) {
+            if at.elapsed() < RUNNING_TTL {
+                return Ok(entry.clone());
+            }
+        }
+
+        let user_id = self.current_user_id().await?;
+        let response: TimeEntriesPage = self
+            .get(
+                "time_entries",
+                &[
+                    ("user_id", user_id.to_string()),
+                    ("is_running", "true".to_string()),
+                    ("per_page", "1".to_string()),
+                ],
+            )
+            .await
+            .context("failed to list running time entries")?;
+
+        let entry = response.time_entries.into_iter().next();
+        running
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), entry.clone()));
+        Ok(entry)
+    }
+
+    async fn time_entries(&self, from: NaiveDate, to: NaiveDate) -> anyhow::Result<Vec<TimeEntry>> {
+        self.user_time_entries(self.current_user_id().await?, from, to)
+            .await
+    }
+
+    /// needs an administrator's token for anyone but the current user
+    async fn user_time_entries(
+        &self,
+        user_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<TimeEntry>> {
+        self.list_time_entries(&[
+            ("user_id", user_id.to_string()),
+            ("from", from.to_string()),
+            ("to", to.to_string()),
+        ])
+        .await
+    }
+
+    async fn uninvoiced_time_entries(
+        &self,
+        client_id: i32,
+        through: NaiveDate,
+    ) -> anyhow::Result<Vec<TimeEntry>> {
+        let mut entries = self
+            .list_time_entries(&[
+                ("client_id", client_id.to_string()),
+                ("is_billed", "false".to_string()),
+                ("to", through.to_string()),
+            ])
+            .await?;
+        entries.retain(|e| e.billable);
+
+        Ok(entries)
+    }
+
+    async fn project_assignments(&self) -> anyhow::Result<Vec<ProjectAssignment>> {
+        let mut assignments = Vec::new();
+        let mut page = Some(1);
+
+        while let Some(current_page) = page {
+            let response: ProjectAssignmentsPage = self
+                .get(
+                    "users/me/project_assignments",
+                    &[
+                        ("page", current_page.to_string()),
+                        ("per_page", "100".to_string()),
+                    ],
+                )
+                .await
+                .context("failed to list project assignments")?;
+
+            for assignment in response.project_assignments {
+                if !assignment.is_active {
+                    continue;
+                }
+
+                let tasks = assignment
+                    .task_assignments
+                    .into_iter()
+                    .filter(|t| t.is_active)
+                    .map(|t| TaskAssignment {
+                        task_id: t.task.id,
+                        task_name: t.task.name,
+                        billable: t.billable,
+                    })
+                    .collect();
+
+                assignments.push(ProjectAssignment {
+                    project_id: assignment.project.id,
+                    project_name: assignment.project.name,
+                    client_name: assignment.client.map(|c| c.name),
+                    tasks,
+                });
+            }
+
+            page = response.next_page;
+        }
+
+        Ok(assignments)
+    }
+
+    async fn start_timer(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        spent_date: NaiveDate,
+        notes: Option<String>,
+    ) -> anyhow::Result<TimeEntry> {
+        self.create_time_entry(project_id, task_id, spent_date, None, notes)
+            .await
+            .context("failed to start a timer")
+    }
+
+    async fn restart_time_entry(&self, entry: &TimeEntry) -> anyhow::Result<TimeEntry> {
+        self.patch_time_entry(entry, Some("restart"), None)
+            .await
+            .context("failed to restart the time entry")
+    }
+
+    async fn stop_time_entry(&self, entry: &TimeEntry) -> anyhow::Result<TimeEntry> {
+        self.patch_time_entry(entry, Some("stop"), None)
+            .await
+            .context("failed to stop the time entry")
+    }
+
+    async fn update_time_entry(
+        &self,
+        entry: &TimeEntry,
+        update: EntryUpdate,
+    ) -> anyhow::Result<TimeEntry> {
+        self.patch_time_entry(entry, None, Some(serde_json::to_value(update)?))
+            .await
+            .context("failed to update the time entry")
+    }
+
+    async fn log_time(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        spent_date: NaiveDate,
+        hours: f32,
+        notes: Option<String>,
+    ) -> anyhow::Result<TimeEntry> {
+        self.create_time_entry(project_id, task_id, spent_date, Some(hours), notes)
+            .await
+            .context("failed to create the time entry")
+    }
+
+    async fn time_entry(&self, id: i64) -> anyhow::Result<TimeEntry> {
+        self.get(&format!("time_entries/{}", id), &[])
+            .await
+            .with_context(|| format!("failed to retrieve time entry {}", id))
+    }
+
+    async fn delete_time_entry(&self, entry: &TimeEntry) -> anyhow::Result<()> {
+        forget_running_time_entry();
+        // harvest answers with an empty body rather than json
+        self.builder(Method::DELETE, &format!("time_entries/{}", entry.id))
+            .await
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("failed to delete the time entry")?;
+        Ok(())
+    }
+
+    async fn wants_timestamp_timers(&self) -> anyhow::Result<bool> {
+        Ok(self.company().await?.wants_timestamp_timers)
+    }
+
+    async fn clients(&self) -> anyhow::Result<Vec<Named>> {
+        let mut clients = Vec::new();
+        let mut page = Some(1);
+
+        while let Some(current_page) = page {
+            let response: ClientsPage = self
+                .get(
+                    "clients",
+                    &[
+                        ("is_active", "true".to_string()),
+                        ("page", current_page.to_string()),
+                        ("per_page", "100".to_string()),
+                    ],
+                )
+                .await
+                .context("failed to list clients")?;
+
+            clients.extend(response.clients);
+            page = response.next_page;
+        }
+
+        Ok(clients)
+    }
+
+    /// harvest fills the invoice with line items for the projects' time,
+    /// summarized by task
+    async fn draft_invoice(
+        &self,
+        client_id: i32,
+        project_ids: Vec<i32>,
+        through: NaiveDate,
+        issue_date: NaiveDate,
+    ) -> anyhow::Result<Invoice> {
+        let invoice: HarvestInvoice = self
+            .request(
+                Method::POST,
+                "invoices",
+                &[],
+                Some(json!({
+                    "client_id": client_id,
+                    "issue_date": issue_date.to_string(),
+                    "line_items_import": {
+                        "project_ids": project_ids,
+                        "time": {
+                            "summary_type": "task",
+                            "to": through.to_string(),
+                        },
+                    },
+                })),
+            )
+            .await
+            .context("failed to create the invoice")?;
+        let company = self.company().await?;
+
+        Ok(Invoice {
+            number: invoice.number,
+            url: format!(
+                "{}/invoices/{}",
+                company.base_uri.trim_end_matches('/'),
+                invoice.id
+            ),
+        })
+    }
+
+    /// needs an administrator's token
+    async fn user_ids(&self) -> anyhow::Result<Vec<i32>> {
+        let mut ids = Vec::new();
+        let mut page = Some(1);
+
+        while let Some(current_page) = page {
+            let response: UsersPage = self
+                .get(
+                    "users",
+                    &[
+                        ("is_active", "true".to_string()),
+                        ("page", current_page.to_string()),
+                        ("per_page", "100".to_string()),
+                    ],
+                )
+                .await
+                .context("failed to list users, which needs an administrator's token")?;
+
+            ids.extend(response.users.into_iter().map(|u| u.id));
+            page = response.next_page;
+        }
+
+        Ok(ids)
+    }
+}
+
+/// whether a request failed because harvest couldn't be reached, rather than
+/// refusing it
+pub fn is_unreachable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_connect() || e.is_timeout())
+    })
+}
@@ -0,0 +1,77 @@
+use glob::MatchOptions;
+
+use crate::project::Project;
+
+/// the frontmost application and the title of its front window, which is empty
+/// when it has none or the terminal isn't allowed to read it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub struct FrontWindow {
+    pub app: String,
+    pub title: String,
+}
+
+/// asks System Events for the frontmost window on macOS, reading titles needs
+/// the accessibility permission. None elsewhere, or when it can't be told
+pub async fn front_window() -> Option<FrontWindow> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = [
+            "tell application \"System Events\"",
+            "set frontApp to first application process whose frontmost is true",
+            "set appName to name of frontApp",
+            "set windowTitle to \"\"",
+            "try",
+            "set windowTitle to name of front window of frontApp",
+            "end try",
+            "end tell",
+            "return appName & tab & windowTitle",
+        ];
+        let output = tokio::process::Command::new("osascript")
+            .args(script.iter().flat_map(|line| ["-e", line]))
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (app, title) = stdout.trim_end().split_once('\t')?;
+        Some(FrontWindow {
+            app: app.to_string(),
+            title: title.to_string(),
+        })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        None
+    }
+}
+
+/// the project a window is work on: the first whose `window_titles` match its
+/// title, or else the first listing its app in `apps`
+pub fn project_for_window<'a>(
+    projects: &'a [Project],
+    window: &FrontWindow,
+) -> Option<&'a Project> {
+    let options = MatchOptions {
+        case_sensitive: false,
+        ..Default::default()
+    };
+    projects
+        .iter()
+        .find(|p| {
+            !window.title.is_empty()
+                && p.window_titles
+                    .iter()
+                    .any(|pattern| pattern.matches_with(&window.title, options))
+        })
+        .or_else(|| {
+            projects.iter().find(|p| {
+                p.apps
+                    .iter()
+                    .any(|app| app.eq_ignore_ascii_case(&window.app))
+            })
+        })
+}
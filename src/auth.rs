@@ -0,0 +1,668 @@
+use std::{
+    env, fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::{
+    api::Harvest,
+    config::{CredentialSettings, Settings},
+    encryption, prompt,
+    tracker::TimeTracker,
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AccessTokenConfig {
+    pub token: String,
+    pub account_id: i32,
+}
+
+impl AccessTokenConfig {
+    /// the time tracker this token is for
+    pub fn tracker(&self) -> Arc<dyn TimeTracker> {
+        Arc::new(Harvest::new(&self.token, self.account_id))
+    }
+}
+
+const HARVEST_ID_URL: &str = "https://id.getharvest.com";
+
+/// access tokens are refreshed when they expire within this many seconds
+const REFRESH_MARGIN_SECS: i64 = 60 * 60;
+
+/// tokens obtained through the oauth2 authorization code flow
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OAuthToken {
+    pub client_id: String,
+    pub client_secret: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// unix timestamp after which the access token is rejected
+    pub expires_at: i64,
+    pub account_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: i64,
+}
+
+const ACCESS_TOKEN: &str = "access-token";
+const OAUTH_TOKEN: &str = "oauth-token";
+
+/// names a credential for an account, e.g. "access-token.agency"; the
+/// unnamed account keeps the original "access-token" name
+fn credential_name(kind: &str, account: Option<&str>) -> String {
+    match account {
+        Some(account) => format!("{}.{}", kind, account),
+        None => kind.to_string(),
+    }
+}
+
+fn default_account_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("default-account")
+}
+
+/// picks the account named by `--account`, falling back to the one in
+/// clockhand.toml and then the default set with `auth use`
+pub fn resolve_account(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    if let Some(account) = account.or(settings.account.as_deref()) {
+        return Ok(Some(account.to_string()));
+    }
+
+    match fs::read_to_string(default_account_path(config_dir)) {
+        Ok(contents) if !contents.trim().is_empty() => Ok(Some(contents.trim().to_string())),
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).context("failed to read the default account"),
+    }
+}
+
+/// sets the account used when `--account` isn't passed
+pub fn use_account(config_dir: &Path, account: &str) -> anyhow::Result<()> {
+    let path = default_account_path(config_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, account)?;
+    println!("Using account {} by default", account);
+    Ok(())
+}
+
+/// where a credential is stored
+pub enum CredentialLocation {
+    Keychain(String),
+    File(PathBuf),
+}
+
+impl fmt::Display for CredentialLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialLocation::Keychain(name) => {
+                write!(f, "keychain item {}/{}", env!("CARGO_PKG_NAME"), name)
+            }
+            CredentialLocation::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl CredentialLocation {
+    fn write(&self, contents: &str) -> anyhow::Result<()> {
+        match self {
+            CredentialLocation::Keychain(name) => keychain_entry(name)?
+                .set_password(contents)
+                .with_context(|| format!("failed to write {}", self)),
+            CredentialLocation::File(path) => encryption::write(path, contents),
+        }
+    }
+}
+
+fn credential_path(config_dir: &Path, name: &str) -> PathBuf {
+    config_dir.join(format!("{}.json", name))
+}
+
+/// path of the plaintext personal access token file for an account
+pub fn access_token_file(config_dir: &Path, account: Option<&str>) -> PathBuf {
+    credential_path(config_dir, &credential_name(ACCESS_TOKEN, account))
+}
+
+fn keychain_entry(name: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(env!("CARGO_PKG_NAME"), name)
+}
+
+/// reads a credential from the keychain, treating an unavailable keychain the
+/// same as a missing item
+fn read_keychain(name: &str) -> anyhow::Result<Option<String>> {
+    match keychain_entry(name).and_then(|entry| entry.get_password()) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(
+            keyring::Error::NoEntry
+            | keyring::Error::PlatformFailure(_)
+            | keyring::Error::NoStorageAccess(_),
+        ) => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("failed to read {} from the keychain", name)),
+    }
+}
+
+/// reads a credential from the keychain, falling back to the plaintext json
+/// file written by older versions
+fn read_credential(
+    config_dir: &Path,
+    name: &str,
+) -> anyhow::Result<Option<(String, CredentialLocation)>> {
+    if let Some(contents) = read_keychain(name)? {
+        return Ok(Some((
+            contents,
+            CredentialLocation::Keychain(name.to_string()),
+        )));
+    }
+
+    let path = credential_path(config_dir, name);
+    Ok(encryption::read_to_string(&path)?
+        .map(|contents| (contents, CredentialLocation::File(path))))
+}
+
+/// saves a credential to the keychain, or to a json file when there is no
+/// keychain available
+fn write_credential(
+    config_dir: &Path,
+    name: &str,
+    contents: &str,
+) -> anyhow::Result<CredentialLocation> {
+    let keychain = CredentialLocation::Keychain(name.to_string());
+    match keychain.write(contents) {
+        Ok(()) => Ok(keychain),
+        Err(e) => {
+            eprintln!("{:#}, saving to a file instead", e);
+            let file = CredentialLocation::File(credential_path(config_dir, name));
+            file.write(contents)?;
+            Ok(file)
+        }
+    }
+}
+
+const TOKEN_ENV: &str = "CLOCKHAND_HARVEST_TOKEN";
+const ACCOUNT_ID_ENV: &str = "CLOCKHAND_HARVEST_ACCOUNT_ID";
+
+/// reads credentials from the environment, for scripts and containers that
+/// shouldn't write them to disk
+fn env_credentials() -> anyhow::Result<Option<AccessTokenConfig>> {
+    match (env::var(TOKEN_ENV).ok(), env::var(ACCOUNT_ID_ENV).ok()) {
+        (Some(token), Some(account_id)) => Ok(Some(AccessTokenConfig {
+            token,
+            account_id: account_id
+                .parse()
+                .with_context(|| format!("{} should be a number", ACCOUNT_ID_ENV))?,
+        })),
+        (Some(_), None) => Err(anyhow!("{} is set but {} isn't", TOKEN_ENV, ACCOUNT_ID_ENV)),
+        (None, Some(_)) => Err(anyhow!("{} is set but {} isn't", ACCOUNT_ID_ENV, TOKEN_ENV)),
+        (None, None) => Ok(None),
+    }
+}
+
+/// reads credentials from the secret provider configured in clockhand.toml,
+/// which may print either a bare token or the same json as access-token.json
+fn provider_credentials(
+    credential_settings: &CredentialSettings,
+) -> anyhow::Result<AccessTokenConfig> {
+    let secret = credential_settings.source.read()?;
+
+    if let Ok(token_data) = serde_json::from_str::<AccessTokenConfig>(&secret) {
+        return Ok(token_data);
+    }
+
+    let token = secret
+        .lines()
+        .next()
+        .filter(|line| !line.is_empty())
+        .ok_or_else(|| anyhow!("the credentials provider didn't print a token"))?
+        .to_string();
+    let account_id = credential_settings.account_id.ok_or_else(|| {
+        anyhow!("set account_id alongside the provider under [credentials] in clockhand.toml")
+    })?;
+
+    Ok(AccessTokenConfig { token, account_id })
+}
+
+/// resolves the credentials to use: environment variables win, then a secret
+/// provider from clockhand.toml, then an oauth token (refreshed when close to
+/// expiry), then a personal access token
+pub async fn credentials(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> anyhow::Result<AccessTokenConfig> {
+    if let Some(token_data) = env_credentials()? {
+        return Ok(token_data);
+    }
+
+    if let Some(credential_settings) = settings.credentials_for(account) {
+        return provider_credentials(credential_settings);
+    }
+
+    let Some((contents, location)) =
+        read_credential(config_dir, &credential_name(OAUTH_TOKEN, account))?
+    else {
+        return access_token(config_dir, account);
+    };
+
+    let mut oauth_token = serde_json::from_str::<OAuthToken>(&contents).with_context(|| {
+        format!(
+            "bad format for {}, run `{} auth oauth` to replace it",
+            location,
+            env!("CARGO_PKG_NAME")
+        )
+    })?;
+
+    if oauth_token.expires_at - chrono::Utc::now().timestamp() < REFRESH_MARGIN_SECS {
+        let response = request_token(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &oauth_token.refresh_token),
+            ("client_id", &oauth_token.client_id),
+            ("client_secret", &oauth_token.client_secret),
+        ])
+        .await
+        .context(format!(
+            "failed to refresh the oauth token, run `{} auth oauth` again",
+            env!("CARGO_PKG_NAME")
+        ))?;
+
+        oauth_token.access_token = response.access_token;
+        oauth_token.refresh_token = response.refresh_token;
+        oauth_token.expires_at = chrono::Utc::now().timestamp() + response.expires_in;
+        location.write(&serde_json::to_string_pretty(&oauth_token)?)?;
+    }
+
+    Ok(AccessTokenConfig {
+        token: oauth_token.access_token,
+        account_id: oauth_token.account_id,
+    })
+}
+
+pub fn access_token(config_dir: &Path, account: Option<&str>) -> anyhow::Result<AccessTokenConfig> {
+    let name = credential_name(ACCESS_TOKEN, account);
+    let Some((contents, location)) = read_credential(config_dir, &name)? else {
+        let path = credential_path(config_dir, &name);
+        let login_args = match account {
+            Some(account) => format!("--account {} auth login", account),
+            None => "auth login".to_string(),
+        };
+        return Err(anyhow!(
+            indoc! {r#"
+                didn't find credentials in the keychain or at {}
+
+                run `{} {}` to create them, or:
+
+                1. visit https://id.getharvest.com/developers
+                2. Create new personal access token
+                3. write json like {{"token": "123...", "account_id": 456}}
+                   into this file: {}
+            "#},
+            path.display(),
+            env!("CARGO_PKG_NAME"),
+            login_args,
+            path.display()
+        ));
+    };
+
+    let token_data = serde_json::from_str::<AccessTokenConfig>(&contents).with_context(|| {
+        format!(
+            indoc! {r#"
+                    bad format for {}
+
+                    expected json like {{"token": "123...", "account_id": 456}},
+                    run `{} auth login` to replace it
+                "#},
+            location,
+            env!("CARGO_PKG_NAME")
+        )
+    })?;
+
+    Ok(token_data)
+}
+
+/// moves credentials for every account from plaintext json files into the
+/// keychain
+pub fn migrate_to_keychain(config_dir: &Path) -> anyhow::Result<()> {
+    if !config_dir.exists() {
+        return Ok(());
+    }
+
+    for dir_entry in fs::read_dir(config_dir)? {
+        let path = dir_entry?.path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .and_then(|f| f.strip_suffix(".json"))
+            .map(|f| f.to_string())
+        else {
+            continue;
+        };
+        if ![ACCESS_TOKEN, OAUTH_TOKEN]
+            .iter()
+            .any(|kind| name == *kind || name.starts_with(&format!("{}.", kind)))
+        {
+            continue;
+        }
+
+        let Some(contents) = encryption::read_to_string(&path)? else {
+            continue;
+        };
+        let keychain = CredentialLocation::Keychain(name.clone());
+        keychain.write(&contents)?;
+
+        // only remove the file once the keychain is known to have it
+        if read_keychain(&name)?.as_deref() != Some(contents.as_str()) {
+            return Err(anyhow!(
+                "{} didn't match after writing, kept {}",
+                keychain,
+                path.display()
+            ));
+        }
+        fs::remove_file(&path)?;
+
+        println!("Moved {} to the {}", path.display(), keychain);
+    }
+
+    Ok(())
+}
+
+/// prompts for a personal access token, checks it against harvest and writes
+/// it to the credentials file
+pub async fn login(config_dir: &Path, account: Option<&str>) -> anyhow::Result<()> {
+    println!(indoc! {"
+        Create a personal access token at https://id.getharvest.com/developers
+        and paste the token and account id below.
+    "});
+
+    let token = rpassword::prompt_password("Token: ")?.trim().to_string();
+    let account_id = prompt::prompt("Account ID: ")?
+        .parse::<i32>()
+        .context("account id should be a number")?;
+
+    let token_data = AccessTokenConfig { token, account_id };
+
+    let me = token_data
+        .tracker()
+        .current_user()
+        .await
+        .context("harvest rejected the token and account id")?;
+
+    let location = write_credential(
+        config_dir,
+        &credential_name(ACCESS_TOKEN, account),
+        &serde_json::to_string_pretty(&token_data)?,
+    )?;
+
+    println!("Logged in as {}, saved to {}", me.name, location);
+
+    Ok(())
+}
+
+/// runs the oauth2 authorization code flow: opens the harvest consent page,
+/// waits for the redirect on a local port and stores the resulting tokens
+pub async fn oauth(
+    config_dir: &Path,
+    account: Option<&str>,
+    client_id: &str,
+    client_secret: &str,
+    port: u16,
+) -> anyhow::Result<()> {
+    // unguessable, so another page can't complete the login with its own code
+    let mut state = [0u8; 16];
+    OsRng.fill_bytes(&mut state);
+    let state: String = state.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let authorize_url = reqwest::Url::parse_with_params(
+        &format!("{}/oauth2/authorize", HARVEST_ID_URL),
+        &[
+            ("client_id", client_id),
+            ("response_type", "code"),
+            ("state", &state),
+        ],
+    )?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("failed to listen on port {}", port))?;
+
+    println!(
+        indoc! {"
+            Opening {}
+
+            The oauth2 application's redirect url must be http://localhost:{}/callback
+        "},
+        authorize_url, port
+    );
+    if open::that(authorize_url.as_str()).is_err() {
+        println!("Couldn't open a browser, visit the url above to continue");
+    }
+
+    let (code, scope) = receive_callback(&listener, &state).await?;
+
+    // scope looks like "harvest:123456", one entry per account granted
+    let account_id = scope
+        .split_whitespace()
+        .find_map(|s| s.strip_prefix("harvest:"))
+        .ok_or_else(|| anyhow!("no harvest account was granted, scope was {:?}", scope))?
+        .parse::<i32>()
+        .context("account id in scope should be a number")?;
+
+    let response = request_token(&[
+        ("grant_type", "authorization_code"),
+        ("code", &code),
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+    ])
+    .await?;
+
+    let oauth_token = OAuthToken {
+        client_id: client_id.to_string(),
+        client_secret: client_secret.to_string(),
+        access_token: response.access_token,
+        refresh_token: response.refresh_token,
+        expires_at: chrono::Utc::now().timestamp() + response.expires_in,
+        account_id,
+    };
+
+    let location = write_credential(
+        config_dir,
+        &credential_name(OAUTH_TOKEN, account),
+        &serde_json::to_string_pretty(&oauth_token)?,
+    )?;
+
+    println!("Authorized account {}, saved to {}", account_id, location);
+
+    Ok(())
+}
+
+/// accepts connections until harvest redirects back with an authorization
+/// code, returning the code and granted scope
+async fn receive_callback(listener: &TcpListener, state: &str) -> anyhow::Result<(String, String)> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+
+        let mut buf = vec![0; 8192];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+
+        // request line is like "GET /callback?code=...&state=... HTTP/1.1"
+        let Some(target) = request
+            .lines()
+            .next()
+            .and_then(|l| l.split_whitespace().nth(1))
+        else {
+            continue;
+        };
+        let url = reqwest::Url::parse(&format!("http://localhost{}", target))?;
+        if url.path() != "/callback" {
+            stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                .await?;
+            continue;
+        }
+
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+        };
+
+        let body = "You can close this window and return to the terminal.";
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .as_bytes(),
+            )
+            .await?;
+
+        if let Some(error) = param("error") {
+            return Err(anyhow!("authorization failed: {}", error));
+        }
+        if param("state").as_deref() != Some(state) {
+            return Err(anyhow!("state in the callback didn't match, try again"));
+        }
+
+        let code = param("code").ok_or_else(|| anyhow!("callback didn't include a code"))?;
+        return Ok((code, param("scope").unwrap_or_default()));
+    }
+}
+
+async fn request_token(params: &[(&str, &str)]) -> anyhow::Result<TokenResponse> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v2/oauth2/token", HARVEST_ID_URL))
+        .header("User-Agent", env!("CARGO_PKG_NAME"))
+        .form(params)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<TokenResponse>()
+        .await?;
+
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountsResponse {
+    accounts: Vec<HarvestAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarvestAccount {
+    id: i64,
+    name: String,
+    product: String,
+}
+
+/// the accounts a token can access, which is what harvest scopes it to
+async fn list_accounts(token: &str) -> anyhow::Result<Vec<HarvestAccount>> {
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v2/accounts", HARVEST_ID_URL))
+        .header("User-Agent", env!("CARGO_PKG_NAME"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .context("couldn't reach harvest, check the network connection")?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(anyhow!(
+            indoc! {"
+                harvest rejected the token (401), it may have been revoked or expired
+
+                create a new token at https://id.getharvest.com/developers and run
+                `{} auth login`, or `{} auth oauth` if you authorized through oauth
+            "},
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_NAME")
+        ));
+    }
+
+    let accounts = response
+        .error_for_status()?
+        .json::<AccountsResponse>()
+        .await?
+        .accounts;
+
+    Ok(accounts)
+}
+
+/// resolves credentials and checks them against harvest, so problems are
+/// reported before a command starts rather than deep inside it
+pub async fn validated_credentials(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> anyhow::Result<AccessTokenConfig> {
+    let token_data = credentials(config_dir, settings, account).await?;
+    let accounts = list_accounts(&token_data.token).await?;
+
+    if !accounts
+        .iter()
+        .any(|a| a.id == token_data.account_id as i64)
+    {
+        let available = accounts
+            .iter()
+            .map(|a| format!("{} ({})", a.id, a.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        return Err(anyhow!(
+            indoc! {"
+                the token works but can't access account id {}
+
+                accounts available to this token: {}
+                run `{} auth login` with one of those account ids
+            "},
+            token_data.account_id,
+            available,
+            env!("CARGO_PKG_NAME")
+        ));
+    }
+
+    Ok(token_data)
+}
+
+/// prints who the credentials belong to and what they can access
+pub async fn status(token_data: &AccessTokenConfig) -> anyhow::Result<()> {
+    let me = token_data
+        .tracker()
+        .current_user()
+        .await
+        .context("harvest rejected the credentials")?;
+    let accounts = list_accounts(&token_data.token).await?;
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout());
+    writeln!(&mut tw, "name\t{}", me.name)?;
+    writeln!(&mut tw, "email\t{}", me.email)?;
+    writeln!(&mut tw, "user id\t{}", me.id)?;
+    writeln!(&mut tw, "account id\t{}", token_data.account_id)?;
+    for account in accounts {
+        writeln!(
+            &mut tw,
+            "scope\t{}:{} ({})",
+            account.product, account.id, account.name
+        )?;
+    }
+    tw.flush()?;
+
+    Ok(())
+}
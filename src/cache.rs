@@ -0,0 +1,97 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{encryption, tracker::TimeEntry};
+
+/// the current user's time entries as last fetched for `report`, so it can
+/// work without a connection
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EntryCache {
+    /// when each day's entries were last fetched
+    pub fetched: BTreeMap<NaiveDate, DateTime<Utc>>,
+
+    /// entries by harvest id
+    pub entries: BTreeMap<String, TimeEntry>,
+}
+
+impl EntryCache {
+    /// time-entries.json in the config directory, or time-entries-<account>.json
+    /// for a named account
+    fn path(config_dir: &Path, account: Option<&str>) -> PathBuf {
+        match account {
+            Some(account) => config_dir.join(format!("time-entries-{}.json", account)),
+            None => config_dir.join("time-entries.json"),
+        }
+    }
+
+    pub fn load(config_dir: &Path, account: Option<&str>) -> anyhow::Result<EntryCache> {
+        let path = EntryCache::path(config_dir, account);
+        match encryption::read_to_string(&path)? {
+            Some(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse {}", path.display())),
+            None => Ok(EntryCache::default()),
+        }
+    }
+
+    pub fn save(&self, config_dir: &Path, account: Option<&str>) -> anyhow::Result<()> {
+        encryption::write(
+            &EntryCache::path(config_dir, account),
+            &serde_json::to_string(self)?,
+        )
+    }
+
+    /// replaces the cached entries between two dates, inclusive, with ones
+    /// just fetched
+    pub fn store(&mut self, from: NaiveDate, to: NaiveDate, entries: &[TimeEntry]) {
+        self.entries
+            .retain(|_, entry| !(from <= entry.spent_date && entry.spent_date <= to));
+        for entry in entries.iter() {
+            self.entries.insert(entry.id.to_string(), entry.clone());
+        }
+
+        let now = Utc::now();
+        for day in from.iter_days().take_while(|d| *d <= to) {
+            self.fetched.insert(day, now);
+        }
+    }
+
+    /// the cached entries between two dates, inclusive, warning on stderr
+    /// about how old they are and which days were never fetched
+    pub fn entries(&self, from: NaiveDate, to: NaiveDate) -> Vec<TimeEntry> {
+        let days: Vec<NaiveDate> = from.iter_days().take_while(|d| *d <= to).collect();
+        let missing = days
+            .iter()
+            .filter(|d| !self.fetched.contains_key(d))
+            .count();
+        let oldest = days.iter().filter_map(|d| self.fetched.get(d)).min();
+
+        match oldest {
+            Some(oldest) => eprintln!(
+                "using entries cached as of {}",
+                oldest.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ),
+            None => eprintln!("no entries are cached for {} to {}", from, to),
+        }
+        if missing > 0 && oldest.is_some() {
+            eprintln!("{} of the {} days haven't been cached", missing, days.len());
+        }
+
+        let mut entries: Vec<TimeEntry> = self
+            .entries
+            .values()
+            .filter(|entry| from <= entry.spent_date && entry.spent_date <= to)
+            .cloned()
+            .collect();
+        // newest first, as harvest lists them
+        entries.sort_by_key(|e| Reverse((e.spent_date, e.created_at)));
+        entries
+    }
+}
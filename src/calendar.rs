@@ -0,0 +1,797 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::{config::Settings, logging};
+
+/// how often calendars are read again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// windows timezone names, which outlook and exchange write as TZIDs, and the
+/// IANA zones CLDR maps them to
+const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("US Eastern Standard Time", "America/Indiana/Indianapolis"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("Argentina Standard Time", "America/Argentina/Buenos_Aires"),
+    ("Azores Standard Time", "Atlantic/Azores"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("FLE Standard Time", "Europe/Kiev"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("Nepal Standard Time", "Asia/Kathmandu"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("Myanmar Standard Time", "Asia/Yangon"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+];
+
+/// TZIDs that have been warned about, so each only is once
+static UNKNOWN_ZONES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// a meeting on today's calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meeting {
+    pub title: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl Meeting {
+    pub fn hours(&self) -> f32 {
+        (self.end - self.start).num_minutes() as f32 / 60.0
+    }
+}
+
+/// today's meetings from the calendars in [calendar], read again every
+/// `REFRESH_INTERVAL`
+#[derive(Default)]
+pub struct Calendar {
+    meetings: Vec<Meeting>,
+    read: Option<Instant>,
+
+    /// meetings that have been offered to be logged
+    offered: HashSet<(String, DateTime<Local>)>,
+}
+
+impl Calendar {
+    /// reads the calendars again if it's been a while, keeping the meetings
+    /// already known when one can't be read
+    pub async fn refresh(&mut self, settings: &Settings) {
+        if settings.calendar.ics.is_empty()
+            || self
+                .read
+                .is_some_and(|read| read.elapsed() < REFRESH_INTERVAL)
+        {
+            return;
+        }
+        self.read = Some(Instant::now());
+
+        let day = settings.today();
+        let mut meetings = Vec::new();
+        for source in settings.calendar.ics.iter() {
+            match read(source).await.and_then(|ics| parse(&ics)) {
+                Ok(events) => meetings.extend(events.iter().flat_map(|e| e.occurrences_on(day))),
+                Err(e) => {
+                    logging::warn!("couldn't read the calendar {}: {:#}", source, e);
+                    return;
+                }
+            }
+        }
+        meetings.sort_by_key(|m| m.start);
+        self.meetings = meetings;
+    }
+
+    /// the meeting going on at `at`, if any
+    pub fn current(&self, at: DateTime<Local>) -> Option<&Meeting> {
+        self.meetings.iter().find(|m| m.start <= at && at < m.end)
+    }
+
+    /// meetings that ended between `since` and `at` and haven't been returned
+    /// before
+    pub fn take_ended(&mut self, since: DateTime<Local>, at: DateTime<Local>) -> Vec<Meeting> {
+        let offered = &mut self.offered;
+        self.meetings
+            .iter()
+            .filter(|m| since <= m.end && m.end <= at)
+            .filter(|m| offered.insert((m.title.clone(), m.start)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// downloads an ics url, or reads a file
+async fn read(source: &str) -> anyhow::Result<String> {
+    if let Some(rest) = source.strip_prefix("webcal://") {
+        return download(&format!("https://{}", rest)).await;
+    }
+    if source.starts_with("https://") || source.starts_with("http://") {
+        return download(source).await;
+    }
+
+    let path = match source.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .ok_or_else(|| anyhow!("couldn't find the home directory"))?
+            .join(rest),
+        None => PathBuf::from(source),
+    };
+    std::fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+async fn download(url: &str) -> anyhow::Result<String> {
+    Ok(reqwest::get(url).await?.error_for_status()?.text().await?)
+}
+
+/// the timezone an event's times are written in
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Utc,
+    Named(Tz),
+
+    /// whatever timezone the reader's in
+    Floating,
+}
+
+impl Zone {
+    fn local(self, at: NaiveDateTime) -> Option<DateTime<Local>> {
+        match self {
+            Zone::Utc => Some(Utc.from_utc_datetime(&at).with_timezone(&Local)),
+            Zone::Named(tz) => Some(
+                tz.from_local_datetime(&at)
+                    .earliest()?
+                    .with_timezone(&Local),
+            ),
+            Zone::Floating => Local.from_local_datetime(&at).earliest(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Frequency {
+    Daily,
+    Weekly,
+}
+
+/// the parts of an RRULE that are understood: daily and weekly repeats.
+/// events repeating some other way only have their first occurrence
+#[derive(Debug, Clone)]
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    until: Option<DateTime<Local>>,
+    count: Option<u32>,
+    days: Vec<Weekday>,
+}
+
+/// a VEVENT with a time, as opposed to a whole day
+#[derive(Debug, Clone)]
+struct Event {
+    uid: String,
+    title: String,
+    start: NaiveDateTime,
+    zone: Zone,
+    length: chrono::Duration,
+    rule: Option<Rule>,
+
+    /// occurrences left out, by EXDATE or as they were moved
+    except: Vec<DateTime<Local>>,
+}
+
+impl Event {
+    /// occurrences overlapping `day`
+    fn occurrences_on(&self, day: NaiveDate) -> Vec<Meeting> {
+        let (Some(from), Some(to)) = (
+            Zone::Floating.local(day.and_hms_opt(0, 0, 0).unwrap()),
+            Zone::Floating.local(day.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap()),
+        ) else {
+            return Vec::new();
+        };
+
+        let mut meetings = Vec::new();
+        for start in self.starts() {
+            let Some(start) = self.zone.local(start) else {
+                continue;
+            };
+            if start >= to {
+                break;
+            }
+            let end = start + self.length;
+            if end > from && !self.except.contains(&start) {
+                meetings.push(Meeting {
+                    title: self.title.clone(),
+                    start,
+                    end,
+                });
+            }
+        }
+        meetings
+    }
+
+    /// when each occurrence starts, in order
+    fn starts(&self) -> Box<dyn Iterator<Item = NaiveDateTime> + '_> {
+        let Some(rule) = &self.rule else {
+            return Box::new(std::iter::once(self.start));
+        };
+
+        let candidates: Box<dyn Iterator<Item = NaiveDateTime> + '_> = match rule.frequency {
+            Frequency::Daily => Box::new(
+                (0..).map(move |n| self.start + chrono::Duration::days(n * rule.interval as i64)),
+            ),
+            Frequency::Weekly => {
+                let mut days = if rule.days.is_empty() {
+                    vec![self.start.weekday()]
+                } else {
+                    rule.days.clone()
+                };
+                days.sort_by_key(|d| d.num_days_from_monday());
+                let monday = self.start.date()
+                    - chrono::Duration::days(self.start.weekday().num_days_from_monday() as i64);
+                Box::new(
+                    (0..)
+                        .flat_map(move |week| {
+                            let days = days.clone();
+                            days.into_iter().map(move |day| {
+                                (monday
+                                    + chrono::Duration::weeks(week * rule.interval as i64)
+                                    + chrono::Duration::days(day.num_days_from_monday() as i64))
+                                .and_time(self.start.time())
+                            })
+                        })
+                        .filter(move |start| *start >= self.start),
+                )
+            }
+        };
+
+        Box::new(
+            candidates
+                .take(rule.count.map_or(usize::MAX, |count| count as usize))
+                .take_while(move |start| {
+                    rule.until
+                        .is_none_or(|until| self.zone.local(*start).is_none_or(|s| s <= until))
+                }),
+        )
+    }
+}
+
+/// a content line: its name, parameters and value
+struct Property<'a> {
+    name: &'a str,
+    params: Vec<(&'a str, &'a str)>,
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let (head, value) = line.split_once(':')?;
+        let mut parts = head.split(';');
+        let name = parts.next()?;
+        let params = parts
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k, v.trim_matches('"')))
+            .collect();
+        Some(Property {
+            name,
+            params,
+            value,
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&'a str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+    }
+
+    /// a DATE-TIME value and its timezone, or None for a DATE
+    fn date_time(&self, value: &str) -> Option<(NaiveDateTime, Zone)> {
+        if let Some(utc) = value.strip_suffix('Z') {
+            return Some((
+                NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?,
+                Zone::Utc,
+            ));
+        }
+        let zone = match self.param("TZID") {
+            Some(tzid) => match named_zone(tzid) {
+                Some(tz) => Zone::Named(tz),
+                None => {
+                    let mut unknown = UNKNOWN_ZONES.lock().unwrap();
+                    if !unknown.iter().any(|z| z == tzid) {
+                        logging::warn!(
+                            "unknown calendar timezone {:?}, reading its times as local",
+                            tzid
+                        );
+                        unknown.push(tzid.to_string());
+                    }
+                    Zone::Floating
+                }
+            },
+            None => Zone::Floating,
+        };
+        Some((
+            NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?,
+            zone,
+        ))
+    }
+}
+
+/// an IANA timezone, or the one a windows timezone name maps to
+fn named_zone(tzid: &str) -> Option<Tz> {
+    tzid.parse().ok().or_else(|| {
+        WINDOWS_ZONES
+            .iter()
+            .find(|(windows, _)| *windows == tzid)
+            .and_then(|(_, iana)| iana.parse().ok())
+    })
+}
+
+/// joins lines folded onto the next with leading whitespace
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// the timed, busy events in a calendar, leaving out whole days, cancelled
+/// events and ones marked free
+fn parse(ics: &str) -> anyhow::Result<Vec<Event>> {
+    let lines = unfold(ics);
+    if !lines.iter().any(|l| l.trim() == "BEGIN:VCALENDAR") {
+        return Err(anyhow!("it isn't an ics calendar"));
+    }
+
+    let mut events = Vec::new();
+    // occurrences of repeating events moved or cancelled, by uid
+    let mut moved: Vec<(String, DateTime<Local>)> = Vec::new();
+
+    let mut properties: Option<Vec<Property>> = None;
+    // alarms and the like inside an event have properties of their own
+    let mut depth = 0;
+    for line in lines.iter().map(|l| l.trim_end()) {
+        match line {
+            "BEGIN:VEVENT" => {
+                properties = Some(Vec::new());
+                depth = 0;
+            }
+            "END:VEVENT" => {
+                if let Some(properties) = properties.take() {
+                    if let Some(event) = event(&properties, &mut moved) {
+                        events.push(event);
+                    }
+                }
+            }
+            _ if properties.is_some() && line.starts_with("BEGIN:") => depth += 1,
+            _ if properties.is_some() && line.starts_with("END:") => depth -= 1,
+            _ if depth == 0 => {
+                if let (Some(properties), Some(property)) =
+                    (properties.as_mut(), Property::parse(line))
+                {
+                    properties.push(property);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for event in events.iter_mut() {
+        event.except.extend(
+            moved
+                .iter()
+                .filter(|(uid, _)| *uid == event.uid && event.rule.is_some())
+                .map(|(_, at)| *at),
+        );
+    }
+    Ok(events)
+}
+
+fn event(properties: &[Property], moved: &mut Vec<(String, DateTime<Local>)>) -> Option<Event> {
+    let get = |name: &str| {
+        properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    };
+    let uid = get("UID").map_or(String::new(), |p| p.value.to_string());
+
+    if let Some(recurrence_id) = get("RECURRENCE-ID") {
+        if let Some((at, zone)) = recurrence_id.date_time(recurrence_id.value) {
+            if let Some(at) = zone.local(at) {
+                moved.push((uid.clone(), at));
+            }
+        }
+    }
+    if get("STATUS").is_some_and(|p| p.value.eq_ignore_ascii_case("CANCELLED"))
+        || get("TRANSP").is_some_and(|p| p.value.eq_ignore_ascii_case("TRANSPARENT"))
+    {
+        return None;
+    }
+
+    let dtstart = get("DTSTART")?;
+    let (start, zone) = dtstart.date_time(dtstart.value)?;
+    let length = match (get("DTEND"), get("DURATION")) {
+        (Some(dtend), _) => {
+            let (end, end_zone) = dtend.date_time(dtend.value)?;
+            end_zone.local(end)? - zone.local(start)?
+        }
+        (None, Some(duration)) => parse_duration(duration.value)?,
+        (None, None) => chrono::Duration::zero(),
+    };
+
+    let except = properties
+        .iter()
+        .filter(|p| p.name.eq_ignore_ascii_case("EXDATE"))
+        .flat_map(|p| {
+            p.value
+                .split(',')
+                .filter_map(|value| p.date_time(value))
+                .filter_map(|(at, zone)| zone.local(at))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Some(Event {
+        uid,
+        title: get("SUMMARY").map_or(String::new(), |p| unescape(p.value)),
+        start,
+        zone,
+        length,
+        rule: get("RRULE").and_then(|p| parse_rule(p.value, zone)),
+        except,
+    })
+}
+
+fn parse_rule(value: &str, zone: Zone) -> Option<Rule> {
+    let parts: Vec<(&str, &str)> = value.split(';').filter_map(|p| p.split_once('=')).collect();
+    let get = |name: &str| parts.iter().find(|(k, _)| *k == name).map(|(_, v)| *v);
+
+    let frequency = match get("FREQ")? {
+        "DAILY" => Frequency::Daily,
+        "WEEKLY" => Frequency::Weekly,
+        _ => return None,
+    };
+    let until = get("UNTIL").and_then(|until| match until.strip_suffix('Z') {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .ok()
+            .and_then(|at| Zone::Utc.local(at)),
+        None => NaiveDateTime::parse_from_str(until, "%Y%m%dT%H%M%S")
+            .ok()
+            // a date is the last day with an occurrence
+            .or_else(|| {
+                NaiveDate::parse_from_str(until, "%Y%m%d")
+                    .ok()?
+                    .and_hms_opt(23, 59, 59)
+            })
+            .and_then(|at| zone.local(at)),
+    });
+    let days = get("BYDAY")
+        .map(|days| {
+            days.split(',')
+                .filter_map(|day| match day {
+                    "MO" => Some(Weekday::Mon),
+                    "TU" => Some(Weekday::Tue),
+                    "WE" => Some(Weekday::Wed),
+                    "TH" => Some(Weekday::Thu),
+                    "FR" => Some(Weekday::Fri),
+                    "SA" => Some(Weekday::Sat),
+                    "SU" => Some(Weekday::Sun),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Rule {
+        frequency,
+        interval: get("INTERVAL")
+            .and_then(|i| i.parse().ok())
+            .unwrap_or(1)
+            .max(1),
+        until,
+        count: get("COUNT").and_then(|c| c.parse().ok()),
+        days,
+    })
+}
+
+/// a DURATION like PT1H30M or P1D
+fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let value = value.strip_prefix('P')?;
+    let mut total = chrono::Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = std::mem::take(&mut number).parse().ok()?;
+                total += match unit {
+                    'W' => chrono::Duration::weeks(n),
+                    'D' => chrono::Duration::days(n),
+                    'H' => chrono::Duration::hours(n),
+                    'M' => chrono::Duration::minutes(n),
+                    'S' => chrono::Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(total)
+}
+
+/// undoes the escaping of commas, semicolons and newlines in TEXT values
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(events: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}\r\nEND:VCALENDAR\r\n",
+            events.trim().replace('\n', "\r\n")
+        )
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn floating(s: &str) -> DateTime<Local> {
+        let at = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        Zone::Floating.local(at).unwrap()
+    }
+
+    #[test]
+    fn unfolds_continued_lines() {
+        assert_eq!(
+            unfold("SUMMARY:Weekly\r\n  planning\r\n\tmeeting\r\nUID:1"),
+            vec!["SUMMARY:Weekly planningmeeting", "UID:1"]
+        );
+    }
+
+    #[test]
+    fn reads_date_times_in_their_timezones() {
+        let utc = Property::parse("DTSTART:20240108T150000Z").unwrap();
+        let (at, zone) = utc.date_time(utc.value).unwrap();
+        assert_eq!(
+            zone.local(at).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 8, 15, 0, 0).unwrap()
+        );
+
+        let named = Property::parse("DTSTART;TZID=America/New_York:20240108T100000").unwrap();
+        let (at, zone) = named.date_time(named.value).unwrap();
+        assert_eq!(
+            zone.local(at).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 8, 15, 0, 0).unwrap()
+        );
+
+        let windows =
+            Property::parse("DTSTART;TZID=\"Pacific Standard Time\":20240108T070000").unwrap();
+        let (at, zone) = windows.date_time(windows.value).unwrap();
+        assert_eq!(
+            zone.local(at).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 8, 15, 0, 0).unwrap()
+        );
+
+        for line in [
+            "DTSTART:20240108T100000",
+            "DTSTART;TZID=Somewhere/Unknown:20240108T100000",
+        ] {
+            let floating_time = Property::parse(line).unwrap();
+            let (at, zone) = floating_time.date_time(floating_time.value).unwrap();
+            assert!(matches!(zone, Zone::Floating), "{}", line);
+            assert_eq!(zone.local(at).unwrap(), floating("2024-01-08 10:00"));
+        }
+
+        let day = Property::parse("DTSTART;VALUE=DATE:20240108").unwrap();
+        assert!(day.date_time(day.value).is_none());
+    }
+
+    #[test]
+    fn maps_every_windows_zone() {
+        for (windows, iana) in WINDOWS_ZONES {
+            assert!(named_zone(windows).is_some(), "{} -> {}", windows, iana);
+        }
+    }
+
+    #[test]
+    fn skips_all_day_cancelled_and_free_events() {
+        let events = parse(&calendar(
+            "
+BEGIN:VEVENT
+UID:all-day
+SUMMARY:Holiday
+DTSTART;VALUE=DATE:20240108
+DTEND;VALUE=DATE:20240109
+END:VEVENT
+BEGIN:VEVENT
+UID:cancelled
+SUMMARY:Cancelled
+STATUS:CANCELLED
+DTSTART:20240108T090000
+DTEND:20240108T093000
+END:VEVENT
+BEGIN:VEVENT
+UID:free
+SUMMARY:Free
+TRANSP:TRANSPARENT
+DTSTART:20240108T090000
+DTEND:20240108T093000
+END:VEVENT
+BEGIN:VEVENT
+UID:meeting
+SUMMARY:Standup\\, daily
+DTSTART:20240108T090000
+DURATION:PT15M
+BEGIN:VALARM
+SUMMARY:Alarm
+END:VALARM
+END:VEVENT
+",
+        ))
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].occurrences_on(date("2024-01-08")),
+            vec![Meeting {
+                title: "Standup, daily".to_string(),
+                start: floating("2024-01-08 09:00"),
+                end: floating("2024-01-08 09:15"),
+            }]
+        );
+        assert!(parse("not a calendar").is_err());
+    }
+
+    #[test]
+    fn repeats_daily_with_an_interval_and_count() {
+        let rule = parse_rule("FREQ=DAILY;INTERVAL=2;COUNT=3", Zone::Floating).unwrap();
+        let event = Event {
+            uid: String::new(),
+            title: String::new(),
+            start: date("2024-01-08").and_hms_opt(9, 0, 0).unwrap(),
+            zone: Zone::Floating,
+            length: chrono::Duration::minutes(30),
+            rule: Some(rule),
+            except: Vec::new(),
+        };
+        let days: Vec<_> = event.starts().map(|start| start.date()).collect();
+        assert_eq!(
+            days,
+            vec![date("2024-01-08"), date("2024-01-10"), date("2024-01-12")]
+        );
+    }
+
+    #[test]
+    fn repeats_weekly_on_days_until_a_date() {
+        let events = parse(&calendar(
+            "
+BEGIN:VEVENT
+UID:weekly
+SUMMARY:Planning
+DTSTART;TZID=Etc/UTC:20240103T100000
+DTEND;TZID=Etc/UTC:20240103T110000
+RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20240117T100000Z
+END:VEVENT
+",
+        ))
+        .unwrap();
+        let days: Vec<_> = events[0].starts().map(|start| start.date()).collect();
+        // the monday before the first wednesday is skipped, and the until time
+        // is included
+        assert_eq!(
+            days,
+            vec![date("2024-01-03"), date("2024-01-15"), date("2024-01-17")]
+        );
+    }
+
+    #[test]
+    fn leaves_out_excluded_and_moved_occurrences() {
+        let events = parse(&calendar(
+            "
+BEGIN:VEVENT
+UID:daily
+SUMMARY:Standup
+DTSTART:20240108T090000
+DTEND:20240108T091500
+RRULE:FREQ=DAILY
+EXDATE:20240109T090000,20240111T090000
+END:VEVENT
+BEGIN:VEVENT
+UID:daily
+SUMMARY:Standup
+RECURRENCE-ID:20240110T090000
+DTSTART:20240110T140000
+DTEND:20240110T141500
+END:VEVENT
+",
+        ))
+        .unwrap();
+        let on = |day| -> Vec<_> {
+            events
+                .iter()
+                .flat_map(|e| e.occurrences_on(date(day)))
+                .map(|m| m.start)
+                .collect()
+        };
+        assert_eq!(on("2024-01-08"), vec![floating("2024-01-08 09:00")]);
+        assert!(on("2024-01-09").is_empty());
+        assert_eq!(on("2024-01-10"), vec![floating("2024-01-10 14:00")]);
+        assert!(on("2024-01-11").is_empty());
+        assert_eq!(on("2024-01-12"), vec![floating("2024-01-12 09:00")]);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(
+            parse_duration("PT1H30M"),
+            Some(chrono::Duration::minutes(90))
+        );
+        assert_eq!(parse_duration("P1D"), Some(chrono::Duration::days(1)));
+        assert_eq!(
+            parse_duration("P1W2DT3S"),
+            Some(chrono::Duration::days(9) + chrono::Duration::seconds(3))
+        );
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("PT5X"), None);
+    }
+}
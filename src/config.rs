@@ -0,0 +1,555 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    encryption::EncryptionSettings,
+    migrate::{self, ConfigKind},
+    notification::Urgency,
+    report::Column,
+    secrets::SecretSource,
+    timer,
+};
+
+/// resolves the directory holding credentials and settings, in order:
+///
+/// 1. the `--config-dir` flag
+/// 2. `$XDG_CONFIG_HOME/clockhand`
+/// 3. `~/.config/clockhand`, if it already exists (where older versions
+///    looked on every platform)
+/// 4. the platform's config directory, e.g. `~/Library/Application Support`
+///    on macOS and `%APPDATA%` on windows
+pub fn config_dir(override_dir: Option<&Path>) -> anyhow::Result<PathBuf> {
+    if let Some(dir) = override_dir {
+        return Ok(dir.to_path_buf());
+    }
+
+    if let Some(xdg_config_home) = env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        return Ok(PathBuf::from(xdg_config_home).join(env!("CARGO_PKG_NAME")));
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        let legacy = home.join(".config").join(env!("CARGO_PKG_NAME"));
+        if legacy.is_dir() {
+            return Ok(legacy);
+        }
+    }
+
+    let dir = dirs::config_dir().ok_or_else(|| anyhow!("couldn't determine a config directory"))?;
+    Ok(dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// global settings read from `clockhand.toml` in the config directory, every
+/// field is optional and command line flags take precedence
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// format version, see migrate.rs
+    pub version: u32,
+
+    pub watch: WatchSettings,
+    pub notifications: NotificationSettings,
+    pub report: ReportSettings,
+
+    /// where to read the token for the default account
+    pub credentials: Option<CredentialSettings>,
+
+    /// settings for accounts named with `--account`
+    pub accounts: BTreeMap<String, AccountSettings>,
+
+    /// account used when `--account` isn't passed, takes precedence over
+    /// `auth use`
+    pub account: Option<String>,
+
+    /// named sets of settings that override the ones above when selected with
+    /// `--profile`
+    pub profiles: BTreeMap<String, toml::Value>,
+
+    pub encryption: EncryptionSettings,
+
+    /// shortcuts for `start` and `log`, e.g. `clockhand start standup`
+    pub aliases: BTreeMap<String, Alias>,
+
+    pub targets: TargetSettings,
+
+    pub theme: ThemeSettings,
+
+    pub schedule: ScheduleSettings,
+
+    pub hooks: HookSettings,
+
+    pub calendar: CalendarSettings,
+
+    /// IANA name of the timezone days and weeks start in for `report` and
+    /// `watch`, e.g. "America/New_York", defaults to the system's
+    pub timezone: Option<Tz>,
+}
+
+/// shell commands `watch` runs on events, given json about the event on stdin
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HookSettings {
+    /// activity in a project without a timer running for it
+    pub on_untracked_activity: Option<String>,
+
+    /// a timer started from a reminder or by `auto_start`
+    pub on_timer_started: Option<String>,
+
+    /// an idle or quiet timer stopped
+    pub on_timer_stopped: Option<String>,
+
+    /// a reminder to start a timer shown
+    pub on_reminder_sent: Option<String>,
+}
+
+/// calendars `watch` reads meetings from, holding off reminders during them
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CalendarSettings {
+    /// urls or paths of ics calendars, e.g. a calendar's secret address
+    pub ics: Vec<String>,
+
+    /// alias, with a project and task, that a meeting is offered to be logged
+    /// to once it's over
+    pub log_alias: Option<String>,
+}
+
+/// when `watch` may send notifications, outside of which it only logs
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScheduleSettings {
+    /// working days, e.g. ["mon", "tue", "wed", "thu", "fri"], every day when
+    /// empty
+    pub days: Vec<Weekday>,
+
+    /// time the working day starts and ends, e.g. "9:00" and "18:00"
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+/// colors for `report`, by name as in "cyan" or "bright black"
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    /// dimmed when not set
+    pub dates: Option<String>,
+
+    /// for entries without notes
+    pub empty_notes: String,
+
+    /// each project gets one of these, picked by its id
+    pub projects: Vec<String>,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        ThemeSettings {
+            dates: None,
+            empty_notes: "red".to_string(),
+            projects: ["cyan", "green", "yellow", "magenta", "blue"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TargetSettings {
+    /// hours a day to aim for across all projects, projects can have their
+    /// own `daily_target` too
+    pub daily: Option<f32>,
+
+    /// hours a week to aim for, shown by `report --summary`
+    pub weekly: Option<f32>,
+
+    /// hours a week available for work, which `report --utilization` measures
+    /// against
+    pub capacity: Option<f32>,
+
+    /// percentage of `capacity` to aim to bill, e.g. 75
+    pub billable: Option<f32>,
+}
+
+/// a harvest project or task, by id or by name
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum NameOrId {
+    Id(i64),
+    Name(String),
+}
+
+impl fmt::Display for NameOrId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameOrId::Id(id) => write!(f, "{}", id),
+            NameOrId::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// a named time entry template, flags passed along with it take precedence
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Alias {
+    pub project: Option<NameOrId>,
+    pub task: Option<NameOrId>,
+    pub notes: Option<String>,
+    pub billable: Option<bool>,
+
+    /// how long `log` records, e.g. "15m"
+    pub duration: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CredentialSettings {
+    /// needed unless the provider prints json with an account_id
+    pub account_id: Option<i32>,
+
+    #[serde(flatten)]
+    pub source: SecretSource,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AccountSettings {
+    pub credentials: Option<CredentialSettings>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WatchSettings {
+    /// seconds between notifications
+    pub interval: Option<u64>,
+
+    /// project config paths watched when none are passed, may use globs and ~
+    pub projects: Vec<String>,
+
+    /// minutes without changes in a project after which the next change
+    /// counts as starting work again, for projects with `auto_start`
+    pub auto_start_gap: Option<u64>,
+
+    /// minutes without keyboard or mouse use after which changes to files
+    /// don't cause reminders, defaults to 5
+    pub away_after: Option<u64>,
+
+    /// minutes without changes in the running timer's project, or keyboard
+    /// and mouse use where that can be told, after which the timer is stopped
+    pub auto_stop_after: Option<u64>,
+
+    /// asks with a notification before stopping an idle timer
+    pub auto_stop_prompt: bool,
+
+    /// leaves an idle timer's entry ending at the project's last change,
+    /// rather than when it was stopped
+    pub auto_stop_backdate: bool,
+
+    /// minutes without changes in the running timer's project after which
+    /// `watch` asks whether work on it is still going
+    pub quiet_after: Option<u64>,
+
+    /// counts using the apps and windows projects list as activity, as
+    /// `--track-apps` does
+    pub track_apps: bool,
+
+    /// how reminders change as they're ignored, the first step applying
+    /// after one is ignored, the second after two and so on, the last one
+    /// staying until a reminder is answered or a timer started
+    pub escalation: Vec<EscalationStep>,
+
+    /// time of the end of day summary, e.g. "17:30", or "end" for the end of
+    /// the [schedule]
+    pub day_summary_at: Option<String>,
+
+    /// time on each weekday, e.g. mon = "9:30", to remind at when no timer's
+    /// been started that day. not on `holidays` in [report]
+    pub start_reminder: HashMap<Weekday, String>,
+
+    /// shows a notification when watch is stopped, by ctrl-c or its service
+    pub notify_on_stop: bool,
+}
+
+/// overrides for a reminder after some have been ignored
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EscalationStep {
+    /// seconds until the next reminder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<Urgency>,
+
+    /// asks with a dialog on screen rather than a notification, on macOS and
+    /// on linux with zenity
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dialog: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub sound: String,
+
+    /// wording of `watch`'s reminders, which a project's `templates` override
+    pub templates: Templates,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            sound: "Sosumi".to_string(),
+            templates: Templates::default(),
+        }
+    }
+}
+
+/// handlebars-style templates for `watch`'s reminders, e.g. "Start a timer
+/// for {{project}}", see template.rs for the variables
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Templates {
+    /// the reminder's title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// the reminder's text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    /// the reminder's text after activity without a timer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub untracked_body: Option<String>,
+}
+
+impl Templates {
+    pub fn is_empty(&self) -> bool {
+        *self == Templates::default()
+    }
+
+    /// these templates, with any that aren't set taken from `fallback`
+    pub fn or(&self, fallback: &Templates) -> Templates {
+        Templates {
+            summary: self.summary.clone().or_else(|| fallback.summary.clone()),
+            body: self.body.clone().or_else(|| fallback.body.clone()),
+            untracked_body: self
+                .untracked_body
+                .clone()
+                .or_else(|| fallback.untracked_body.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReportSettings {
+    pub week_start: Weekday,
+
+    /// days `report --gaps` doesn't expect time on, besides weekends
+    pub holidays: Vec<NaiveDate>,
+
+    /// columns of the text report, e.g. ["date", "project", "hours", "notes"],
+    /// with `--columns` taking precedence
+    pub columns: Option<Vec<Column>>,
+
+    /// how `report --amounts` prints money, with `{}` standing for the amount,
+    /// e.g. "{} €"
+    pub currency_format: String,
+}
+
+impl Default for ReportSettings {
+    fn default() -> Self {
+        ReportSettings {
+            week_start: Weekday::Mon,
+            holidays: Vec::new(),
+            columns: None,
+            currency_format: "${}".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// the day `at` falls on in the configured timezone
+    pub fn date(&self, at: DateTime<Local>) -> NaiveDate {
+        match self.timezone {
+            Some(tz) => at.with_timezone(&tz).date_naive(),
+            None => at.date_naive(),
+        }
+    }
+
+    /// today in the configured timezone
+    pub fn today(&self) -> NaiveDate {
+        self.date(Local::now())
+    }
+
+    /// the time of day `at` is in the configured timezone
+    pub fn time(&self, at: DateTime<Local>) -> NaiveTime {
+        match self.timezone {
+            Some(tz) => at.with_timezone(&tz).time(),
+            None => at.time(),
+        }
+    }
+
+    /// when `watch` sends the end of day summary, if it does
+    pub fn day_summary_time(&self) -> anyhow::Result<Option<NaiveTime>> {
+        let at = match self.watch.day_summary_at.as_deref() {
+            None => return Ok(None),
+            Some("end") => self.schedule.end.as_deref().ok_or_else(|| {
+                anyhow!("day_summary_at in [watch] is \"end\", but [schedule] has no end")
+            })?,
+            Some(at) => at,
+        };
+        Ok(Some(
+            timer::parse_time(at).context("bad day_summary_at in [watch]")?,
+        ))
+    }
+
+    /// whether `at` falls on a working day between the [schedule]'s start and
+    /// end, in the configured timezone
+    pub fn is_working_time(&self, at: DateTime<Local>) -> anyhow::Result<bool> {
+        let (weekday, time) = match self.timezone {
+            Some(tz) => {
+                let at = at.with_timezone(&tz);
+                (at.weekday(), at.time())
+            }
+            None => (at.weekday(), at.time()),
+        };
+        let schedule = &self.schedule;
+
+        if !schedule.days.is_empty() && !schedule.days.contains(&weekday) {
+            return Ok(false);
+        }
+        if let Some(start) = &schedule.start {
+            if time < timer::parse_time(start).context("bad start in [schedule]")? {
+                return Ok(false);
+            }
+        }
+        if let Some(end) = &schedule.end {
+            if time >= timer::parse_time(end).context("bad end in [schedule]")? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// the configured secret provider for an account, if any
+    pub fn credentials_for(&self, account: Option<&str>) -> Option<&CredentialSettings> {
+        match account {
+            Some(account) => self.accounts.get(account)?.credentials.as_ref(),
+            None => self.credentials.as_ref(),
+        }
+    }
+
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(concat!(env!("CARGO_PKG_NAME"), ".toml"))
+    }
+
+    pub fn alias(&self, name: &str) -> anyhow::Result<&Alias> {
+        self.aliases.get(name).ok_or_else(|| {
+            anyhow!(
+                "there's no alias {:?} in {}.toml, aliases are: {}",
+                name,
+                env!("CARGO_PKG_NAME"),
+                self.aliases.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+
+    /// reads the settings file, using defaults when it doesn't exist, with the
+    /// named profile's settings layered on top
+    pub fn load(config_dir: &Path, profile: Option<&str>) -> anyhow::Result<Settings> {
+        let path = Settings::path(config_dir);
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        let contents = if contents.is_empty() {
+            contents
+        } else {
+            migrate::migrate(&path, contents, ConfigKind::Settings)?
+        };
+
+        let mut value = toml::from_str::<toml::Value>(&contents)
+            .with_context(|| format!("bad format for {}", path.display()))?;
+
+        if let Some(profile) = profile {
+            let overrides = value
+                .get("profiles")
+                .and_then(|profiles| profiles.get(profile))
+                .cloned()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "there's no [profiles.{}] section in {}",
+                        profile,
+                        path.display()
+                    )
+                })?;
+            merge(&mut value, overrides);
+        }
+
+        value
+            .try_into()
+            .with_context(|| format!("bad format for {}", path.display()))
+    }
+}
+
+/// recursively overrides values in `base` with the ones in `overrides`, so a
+/// profile only needs to mention what it changes
+fn merge(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// expands a leading ~ and any glob patterns, for paths that didn't go through
+/// a shell
+pub fn expand_paths(patterns: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    for pattern in patterns {
+        let pattern = expand_home(pattern);
+        let matches = glob::glob(&pattern)
+            .with_context(|| format!("bad glob pattern {}", pattern))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if matches.is_empty() {
+            // keep plain paths so a missing file is reported where it's read
+            paths.push(PathBuf::from(pattern));
+        } else {
+            paths.extend(matches);
+        }
+    }
+
+    Ok(paths)
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => path.to_string(),
+    }
+}
@@ -0,0 +1,508 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    activity::TimerState,
+    config::Settings,
+    project::Project,
+    state::{State, WatchState, WatchedProjectState},
+    theme,
+};
+
+/// what the client subcommands of `watch` ask a running watch to do, sent as a
+/// line of json over its control socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Pause,
+    Resume,
+
+    /// a command was run in a directory, sent by the shell hook
+    Activity {
+        path: PathBuf,
+    },
+}
+
+/// marks the events `Request::Activity` passes to the watch loop, alongside
+/// the file watcher's
+pub const SHELL_ACTIVITY: &str = "clockhand-shell-activity";
+
+/// a running watch's state, the reply to every request
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WatchStatus {
+    pub pid: u32,
+
+    /// notifications and auto-starting are paused
+    pub paused: bool,
+
+    pub projects: Vec<WatchedProject>,
+
+    pub last_notification: Option<LastNotification>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedProject {
+    pub name: String,
+    pub harvest_project_id: i32,
+    pub config_path: PathBuf,
+
+    /// when a file in it last changed
+    pub last_event: Option<DateTime<Local>>,
+
+    /// when reminders resume, if they're snoozed
+    pub snoozed_until: Option<DateTime<Utc>>,
+
+    /// seconds of activity since a timer was last seen running for it
+    #[serde(default)]
+    pub untracked_seconds: i64,
+}
+
+impl WatchedProject {
+    pub fn untracked_minutes(&self) -> i64 {
+        self.untracked_seconds / 60
+    }
+}
+
+/// seconds between changes after which they're separate spells of work, and
+/// the time between them isn't counted as activity
+const ACTIVE_GAP: i64 = 5 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastNotification {
+    pub at: DateTime<Local>,
+    pub summary: String,
+    pub body: String,
+}
+
+/// watch's state as shared with the thread answering the control socket
+#[derive(Default, Clone)]
+pub struct Control(Arc<Mutex<WatchStatus>>);
+
+impl Control {
+    pub fn is_paused(&self) -> bool {
+        self.0.lock().unwrap().paused
+    }
+
+    /// replaces the watched projects, keeping when each last changed
+    pub fn set_projects(&self, projects: &[Project]) {
+        let mut status = self.0.lock().unwrap();
+        let previous = std::mem::take(&mut status.projects);
+        status.projects = projects
+            .iter()
+            .map(|project| WatchedProject {
+                name: project.name.clone(),
+                harvest_project_id: project.harvest_project_id,
+                config_path: project.config_path.clone(),
+                last_event: previous
+                    .iter()
+                    .find(|p| p.config_path == project.config_path)
+                    .and_then(|p| p.last_event),
+                snoozed_until: None,
+                untracked_seconds: previous
+                    .iter()
+                    .find(|p| p.config_path == project.config_path)
+                    .map_or(0, |p| p.untracked_seconds),
+            })
+            .collect();
+    }
+
+    /// notes a change in a project, counting the time since the last one as
+    /// untracked activity when they're close together and no timer was
+    /// running for it. a running timer starts the count again
+    pub fn record_event(&self, project: &Project, at: DateTime<Local>, timer: TimerState) {
+        let mut status = self.0.lock().unwrap();
+        if let Some(watched) = status
+            .projects
+            .iter_mut()
+            .find(|p| p.config_path == project.config_path)
+        {
+            match timer {
+                TimerState::Running => watched.untracked_seconds = 0,
+                TimerState::Stopped | TimerState::Other => {
+                    if let Some(last) = watched
+                        .last_event
+                        .filter(|last| (at - *last).num_seconds() <= ACTIVE_GAP)
+                    {
+                        watched.untracked_seconds += (at - last).num_seconds();
+                    }
+                }
+                // without knowing, it's left as it was
+                TimerState::Unknown => {}
+            }
+            watched.last_event = Some(at);
+        }
+    }
+
+    /// minutes of activity in a project since a timer was last running for it
+    pub fn untracked_minutes(&self, project: &Project) -> i64 {
+        self.0
+            .lock()
+            .unwrap()
+            .projects
+            .iter()
+            .find(|p| p.config_path == project.config_path)
+            .map_or(0, WatchedProject::untracked_minutes)
+    }
+
+    /// starts counting untracked activity again, once a timer's running
+    pub fn reset_untracked(&self, project: &Project) {
+        let mut status = self.0.lock().unwrap();
+        if let Some(watched) = status
+            .projects
+            .iter_mut()
+            .find(|p| p.config_path == project.config_path)
+        {
+            watched.untracked_seconds = 0;
+        }
+    }
+
+    /// carries on with the pause, and the last change and untracked activity
+    /// of each project still watched, from a watch that was stopped
+    pub fn restore(&self, saved: &WatchState) {
+        let mut status = self.0.lock().unwrap();
+        status.paused = saved.paused;
+        for watched in status.projects.iter_mut() {
+            if let Some(project) = saved.projects.get(&watched.config_path) {
+                watched.last_event = project.last_event;
+                watched.untracked_seconds = project.untracked_seconds;
+            }
+        }
+    }
+
+    /// the pause and each project's counts, for the next watch to restore
+    pub fn saved_state(&self) -> WatchState {
+        let status = self.0.lock().unwrap();
+        WatchState {
+            stopped_at: Some(Local::now()),
+            paused: status.paused,
+            projects: status
+                .projects
+                .iter()
+                .map(|p| {
+                    (
+                        p.config_path.clone(),
+                        WatchedProjectState {
+                            last_event: p.last_event,
+                            untracked_seconds: p.untracked_seconds,
+                            ignored_reminders: 0,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn record_notification(&self, summary: &str, body: &str) {
+        self.0.lock().unwrap().last_notification = Some(LastNotification {
+            at: Local::now(),
+            summary: summary.to_string(),
+            body: body.to_string(),
+        });
+    }
+
+    /// applies a request, returning the state afterwards with snoozes read
+    /// from state.json
+    fn handle(
+        &self,
+        config_dir: &Path,
+        activity: &UnboundedSender<notify::Result<notify::Event>>,
+        request: Request,
+    ) -> anyhow::Result<WatchStatus> {
+        let mut status = {
+            let mut status = self.0.lock().unwrap();
+            match request {
+                Request::Status => {}
+                Request::Pause => status.paused = true,
+                Request::Resume => status.paused = false,
+                Request::Activity { path } => {
+                    let event = notify::Event::new(notify::EventKind::Other)
+                        .add_path(path)
+                        .set_info(SHELL_ACTIVITY);
+                    activity
+                        .send(Ok(event))
+                        .map_err(|_| anyhow!("watch is shutting down"))?;
+                }
+            }
+            status.clone()
+        };
+
+        let state = State::load(config_dir)?;
+        status.pid = std::process::id();
+        for project in status.projects.iter_mut() {
+            project.snoozed_until = state.snoozed_until(project.harvest_project_id);
+        }
+        Ok(status)
+    }
+}
+
+/// watch.sock in the config directory
+#[cfg(unix)]
+fn socket_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("watch.sock")
+}
+
+/// windows has named pipes in place of unix domain sockets, named after the
+/// config directory so each profile's watch has its own
+#[cfg(windows)]
+fn pipe_name(config_dir: &Path) -> String {
+    format!(
+        r"\\.\pipe\{}-watch-{}",
+        env!("CARGO_PKG_NAME"),
+        crate::activity::hash_path(config_dir)
+    )
+}
+
+/// removes the control socket of a watch that's stopping, a windows pipe goes
+/// away with the process
+pub fn unlisten(config_dir: &Path) {
+    #[cfg(unix)]
+    {
+        let _ = std::fs::remove_file(socket_path(config_dir));
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = config_dir;
+    }
+}
+
+/// answers a request line with a line of json, the same over a socket or pipe
+fn reply(
+    control: &Control,
+    config_dir: &Path,
+    activity: &UnboundedSender<notify::Result<notify::Event>>,
+    line: &str,
+) -> Option<String> {
+    let reply = serde_json::from_str(line)
+        .map_err(anyhow::Error::from)
+        .and_then(|request| control.handle(config_dir, activity, request))
+        .map_err(|e| format!("{:#}", e));
+    serde_json::to_string(&reply).ok()
+}
+
+/// answers requests on the control socket in a background thread, or the
+/// control pipe in a task on windows, failing if another watch already is. activity from the shell hook is sent on to
+/// `activity`, the file watcher's channel
+pub fn listen(
+    config_dir: &Path,
+    control: Control,
+    activity: UnboundedSender<notify::Result<notify::Event>>,
+) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::{
+            io::{BufRead, BufReader},
+            os::unix::net::{UnixListener, UnixStream},
+        };
+
+        let path = socket_path(config_dir);
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(anyhow!(
+                    "watch is already running, see `{} watch status`",
+                    env!("CARGO_PKG_NAME")
+                ));
+            }
+            // left behind by a watch that didn't exit cleanly
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| anyhow!("couldn't listen on {}: {}", path.display(), e))?;
+
+        let config_dir = config_dir.to_path_buf();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut line = String::new();
+                if BufReader::new(&stream).read_line(&mut line).is_err() {
+                    continue;
+                }
+                if let Some(reply) = reply(&control, &config_dir, &activity, &line) {
+                    let _ = writeln!(stream, "{}", reply);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        use tokio::{
+            io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+            net::windows::named_pipe::ServerOptions,
+        };
+
+        let name = pipe_name(config_dir);
+        // only the first instance of a pipe can insist on being first, which
+        // fails while another watch has it
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&name)
+            .map_err(|_| {
+                anyhow!(
+                    "watch is already running, see `{} watch status`",
+                    env!("CARGO_PKG_NAME")
+                )
+            })?;
+
+        let config_dir = config_dir.to_path_buf();
+        tokio::spawn(async move {
+            loop {
+                let connected = server.connect().await;
+                // the next client waits on a new instance while this one's
+                // answered
+                let next = match ServerOptions::new().create(&name) {
+                    Ok(next) => next,
+                    Err(e) => {
+                        crate::logging::error!("couldn't listen on {}: {}", name, e);
+                        return;
+                    }
+                };
+                let client = std::mem::replace(&mut server, next);
+                if connected.is_err() {
+                    continue;
+                }
+
+                let mut client = BufReader::new(client);
+                let mut line = String::new();
+                if client.read_line(&mut line).await.is_err() {
+                    continue;
+                }
+                if let Some(reply) = reply(&control, &config_dir, &activity, &line) {
+                    let _ = client
+                        .get_mut()
+                        .write_all(format!("{}\n", reply).as_bytes())
+                        .await;
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// sends a request to the running watch and returns its state
+pub fn send(config_dir: &Path, request: Request) -> anyhow::Result<WatchStatus> {
+    #[cfg(unix)]
+    {
+        use std::{
+            io::{BufRead, BufReader},
+            os::unix::net::UnixStream,
+        };
+
+        let path = socket_path(config_dir);
+        let mut stream = UnixStream::connect(&path).map_err(|_| {
+            anyhow!(
+                "watch isn't running, there's nothing listening on {}",
+                path.display()
+            )
+        })?;
+        writeln!(stream, "{}", serde_json::to_string(&request)?)?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        let reply: Result<WatchStatus, String> = serde_json::from_str(&line)?;
+        reply.map_err(|e| anyhow!(e))
+    }
+
+    #[cfg(windows)]
+    {
+        use std::{
+            fs::OpenOptions,
+            io::{BufRead, BufReader},
+        };
+
+        // ERROR_PIPE_BUSY, while watch is between answering one client and
+        // waiting on the next
+        const PIPE_BUSY: i32 = 231;
+
+        let name = pipe_name(config_dir);
+        let mut attempts = 0;
+        let mut pipe = loop {
+            match OpenOptions::new().read(true).write(true).open(&name) {
+                Ok(pipe) => break pipe,
+                Err(e) if e.raw_os_error() == Some(PIPE_BUSY) && attempts < 20 => {
+                    attempts += 1;
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                Err(_) => {
+                    return Err(anyhow!(
+                        "watch isn't running, there's nothing listening on {}",
+                        name
+                    ))
+                }
+            }
+        };
+        writeln!(pipe, "{}", serde_json::to_string(&request)?)?;
+
+        let mut line = String::new();
+        BufReader::new(&pipe).read_line(&mut line)?;
+        let reply: Result<WatchStatus, String> = serde_json::from_str(&line)?;
+        reply.map_err(|e| anyhow!(e))
+    }
+}
+
+/// prints the running watch's projects, what last happened in each and its
+/// last notification
+pub fn print_status(settings: &Settings, status: &WatchStatus) -> anyhow::Result<()> {
+    println!(
+        "watch is running (pid {}){}",
+        status.pid,
+        if status.paused {
+            ", notifications are paused"
+        } else {
+            ""
+        }
+    );
+    match &status.last_notification {
+        Some(notification) => println!(
+            "last notification {}: {} - {}",
+            theme::date(
+                &settings.theme,
+                &notification.at.format("%Y-%m-%d %H:%M").to_string()
+            ),
+            notification.summary,
+            notification.body
+        ),
+        None => println!("no notifications yet"),
+    }
+    println!();
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    writeln!(
+        &mut tw,
+        "Project\tLast change\tUntracked\tSnoozed until\tConfig"
+    )?;
+    for project in status.projects.iter() {
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}\t{}\t{}",
+            theme::project(&settings.theme, project.harvest_project_id, &project.name),
+            project
+                .last_event
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            match project.untracked_minutes() {
+                0 => String::new(),
+                minutes => format!("{} min", minutes),
+            },
+            project
+                .snoozed_until
+                .map(|until| until.with_timezone(&Local).format("%H:%M").to_string())
+                .unwrap_or_default(),
+            project.config_path.display()
+        )?;
+    }
+    tw.flush()?;
+    Ok(())
+}
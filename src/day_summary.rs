@@ -0,0 +1,118 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::{
+    auth, config::Settings, control::Control, decimal_hours_to_string, logging, notification,
+    project::Project, report, start_of_week, tracker::TimeEntry,
+};
+
+/// notifies with the hours tracked today per project, activity in watched
+/// projects that's still without a timer, and which workdays this week are
+/// under the daily target. nothing's sent on a day with neither time nor
+/// activity
+pub async fn notify_day_summary(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    projects: &[Project],
+    control: &Control,
+) -> anyhow::Result<()> {
+    let today = settings.today();
+    let week_start = start_of_week(today, settings.report.week_start);
+    let entries = time_entries(config_dir, settings, account, projects, week_start, today).await?;
+
+    let mut hours_by_project: BTreeMap<String, f32> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.spent_date == today) {
+        *hours_by_project
+            .entry(entry.project.name.clone())
+            .or_default() += entry.current_hours();
+    }
+
+    let untracked: Vec<String> = projects
+        .iter()
+        .filter_map(|project| match control.untracked_minutes(project) {
+            0 => None,
+            minutes => Some(format!("{} min on {}", minutes, project.name)),
+        })
+        .collect();
+
+    if hours_by_project.is_empty() && untracked.is_empty() {
+        logging::info!("nothing tracked or untracked today, skipping the day summary");
+        return Ok(());
+    }
+
+    let total: f32 = hours_by_project.values().sum();
+    let mut lines = Vec::new();
+    if !hours_by_project.is_empty() {
+        lines.push(
+            hours_by_project
+                .iter()
+                .map(|(name, hours)| format!("{} {}", name, decimal_hours_to_string(*hours).trim()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+    }
+    if !untracked.is_empty() {
+        lines.push(format!("Without a timer: {}", untracked.join(", ")));
+    }
+
+    let target = settings.targets.daily.unwrap_or(8.0);
+    let short = report::short_days(
+        settings,
+        target,
+        &entries,
+        week_start,
+        today.succ_opt().unwrap(),
+    );
+    if !short.is_empty() {
+        lines.push(format!(
+            "Under {} this week: {}",
+            decimal_hours_to_string(target).trim(),
+            short
+                .iter()
+                .map(|(day, _)| day.weekday().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let summary = format!("{} tracked today", decimal_hours_to_string(total).trim());
+    let body = lines.join("\n");
+    logging::info!("{}: {}", summary, body.replace('\n', "; "));
+    notification::show(&summary, &body, &settings.notifications.sound)?;
+    control.record_notification(&summary, &body);
+
+    Ok(())
+}
+
+/// entries between two days, inclusive, in every account the projects are
+/// billed to
+pub async fn time_entries(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    projects: &[Project],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Vec<TimeEntry>> {
+    let mut accounts = HashSet::new();
+    let mut entries = Vec::new();
+    for project_account in projects
+        .iter()
+        .map(|p| p.account.as_deref().or(account))
+        .chain([account])
+    {
+        if !accounts.insert(project_account) {
+            continue;
+        }
+        let tracker = auth::credentials(config_dir, settings, project_account)
+            .await?
+            .tracker();
+        entries.extend(tracker.time_entries(from, to).await?);
+    }
+    Ok(entries)
+}
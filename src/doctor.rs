@@ -0,0 +1,149 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::anyhow;
+use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    auth, config,
+    config::Settings,
+    migrate::{self, ConfigKind},
+    project::read_project_config,
+};
+
+/// outcome of one check, with a short detail either way
+type CheckResult = Result<String, String>;
+
+fn print_check(name: &str, result: &CheckResult) {
+    match result {
+        Ok(detail) => println!("✓ {}: {}", name, detail),
+        Err(detail) => println!("✗ {}: {}", name, detail),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn check_bundle_identifier() -> CheckResult {
+    let bundle_id = mac_notification_sys::get_bundle_identifier_or_default("Terminal");
+    mac_notification_sys::set_application(&bundle_id)
+        .map(|_| format!("notifications are sent as {}", bundle_id))
+        .map_err(|e| format!("couldn't use {}: {}", bundle_id, e))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn check_bundle_identifier() -> CheckResult {
+    Ok("notifications go to the desktop's notification server".to_string())
+}
+
+fn check_notifications() -> CheckResult {
+    notify_rust::Notification::new()
+        .summary(concat!(env!("CARGO_PKG_NAME"), " doctor"))
+        .body("If you can see this, notifications are allowed")
+        .show()
+        .map(|_| {
+            if cfg!(target_os = "macos") {
+                "sent a test notification, if it didn't appear allow notifications for \
+                 the terminal in system settings"
+            } else {
+                "sent a test notification, if it didn't appear check that a \
+                 notification daemon is running"
+            }
+            .to_string()
+        })
+        .map_err(|e| e.to_string())
+}
+
+async fn check_network() -> CheckResult {
+    // any http response means harvest is reachable, without credentials it's
+    // a 401
+    reqwest::Client::new()
+        .get("https://api.harvestapp.com/v2/users/me")
+        .header("User-Agent", env!("CARGO_PKG_NAME"))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map(|response| format!("api.harvestapp.com responded with {}", response.status()))
+        .map_err(|e| format!("couldn't reach api.harvestapp.com: {}", e))
+}
+
+fn check_watcher() -> CheckResult {
+    let backend = std::any::type_name::<RecommendedWatcher>();
+    let dir = std::env::temp_dir();
+
+    let mut watcher: RecommendedWatcher =
+        Watcher::new(|_: notify::Result<notify::Event>| {}, Config::default())
+            .map_err(|e| format!("couldn't start {}: {}", backend, e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("{} couldn't watch {}: {}", backend, dir.display(), e))?;
+
+    let mut detail = format!("{} is available", backend);
+    if let Ok(max_watches) = std::fs::read_to_string("/proc/sys/fs/inotify/max_user_watches") {
+        detail.push_str(&format!(", max_user_watches is {}", max_watches.trim()));
+    }
+    Ok(detail)
+}
+
+async fn check_credentials(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> CheckResult {
+    auth::validated_credentials(config_dir, settings, account)
+        .await
+        .map(|token_data| format!("account {} accepted the token", token_data.account_id))
+        .map_err(|e| format!("{:#}", e))
+}
+
+fn check_projects(settings: &Settings) -> CheckResult {
+    let paths = config::expand_paths(&settings.watch.projects).map_err(|e| e.to_string())?;
+    if paths.is_empty() {
+        return Ok("no default projects in clockhand.toml".to_string());
+    }
+
+    for path in paths.iter() {
+        migrate::migrate_file(path, ConfigKind::Project)
+            .and_then(|_| read_project_config(path))
+            .map_err(|e| format!("{:#}", e))?;
+    }
+    Ok(format!("{} project config(s) read", paths.len()))
+}
+
+/// runs every check and prints whether it passed
+pub async fn doctor(
+    config_dir: &Path,
+    profile: Option<&str>,
+    account: Option<&str>,
+) -> anyhow::Result<()> {
+    println!("config directory: {}", config_dir.display());
+
+    let settings = migrate::migrate_file(&Settings::path(config_dir), ConfigKind::Settings)
+        .and_then(|_| Settings::load(config_dir, profile));
+    let settings_check = match &settings {
+        Ok(_) => Ok(format!("{} is valid", Settings::path(config_dir).display())),
+        Err(e) => Err(format!("{:#}", e)),
+    };
+    let settings = settings.unwrap_or_default();
+
+    let checks = [
+        ("settings", settings_check),
+        (
+            "credentials",
+            check_credentials(config_dir, &settings, account).await,
+        ),
+        ("projects", check_projects(&settings)),
+        ("network", check_network().await),
+        ("watcher", check_watcher()),
+        ("bundle identifier", check_bundle_identifier()),
+        ("notifications", check_notifications()),
+    ];
+
+    for (name, result) in checks.iter() {
+        print_check(name, result);
+    }
+
+    let failed = checks.iter().filter(|(_, result)| result.is_err()).count();
+    if failed > 0 {
+        return Err(anyhow!("{} check(s) failed", failed));
+    }
+
+    Ok(())
+}
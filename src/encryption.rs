@@ -0,0 +1,284 @@
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::{anyhow, Context};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use serde::Deserialize;
+
+/// marks files written by `write`, so plaintext files from before encryption
+/// was turned on can still be read
+const MAGIC: &[u8] = b"clockhand-encrypted-v1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+const PASSPHRASE_ENV: &str = "CLOCKHAND_PASSPHRASE";
+const KEYCHAIN_KEY_NAME: &str = "encryption-key";
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeySource {
+    /// a random key kept in the system keychain
+    #[default]
+    Keychain,
+
+    /// a key derived from a passphrase, read from CLOCKHAND_PASSPHRASE or
+    /// prompted for
+    Passphrase,
+}
+
+/// the `[encryption]` section of clockhand.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EncryptionSettings {
+    /// encrypt credential files and locally cached data
+    pub enabled: bool,
+    pub key: KeySource,
+}
+
+enum Secret {
+    Key([u8; 32]),
+    Passphrase(String),
+}
+
+static SETTINGS: OnceLock<EncryptionSettings> = OnceLock::new();
+
+/// unlocked lazily, so commands that never touch encrypted files don't prompt
+static SECRET: Mutex<Option<Secret>> = Mutex::new(None);
+
+/// sets how files are encrypted for the rest of the process
+pub fn configure(settings: &EncryptionSettings) {
+    let _ = SETTINGS.set(settings.clone());
+}
+
+fn settings() -> EncryptionSettings {
+    SETTINGS.get().cloned().unwrap_or_default()
+}
+
+fn unlock(key_source: KeySource) -> anyhow::Result<Secret> {
+    match key_source {
+        KeySource::Passphrase => {
+            let passphrase = match env::var(PASSPHRASE_ENV) {
+                Ok(passphrase) => passphrase,
+                Err(_) => {
+                    rpassword::prompt_password(concat!(env!("CARGO_PKG_NAME"), " passphrase: "))?
+                }
+            };
+            Ok(Secret::Passphrase(passphrase))
+        }
+        KeySource::Keychain => {
+            let entry = keyring::Entry::new(env!("CARGO_PKG_NAME"), KEYCHAIN_KEY_NAME)?;
+            match entry.get_password() {
+                Ok(hex) => Ok(Secret::Key(decode_hex(&hex)?)),
+                Err(keyring::Error::NoEntry) => {
+                    let key: [u8; 32] = XChaCha20Poly1305::generate_key(&mut OsRng).into();
+                    entry
+                        .set_password(&encode_hex(&key))
+                        .context("failed to save the encryption key to the keychain")?;
+                    Ok(Secret::Key(key))
+                }
+                Err(e) => Err(e).context("failed to read the encryption key from the keychain"),
+            }
+        }
+    }
+}
+
+/// the key for a file with the given salt
+fn key(salt: &[u8]) -> anyhow::Result<Key> {
+    let mut secret = SECRET.lock().unwrap();
+    if secret.is_none() {
+        *secret = Some(unlock(settings().key)?);
+    }
+
+    match secret.as_ref().unwrap() {
+        Secret::Key(key) => Ok(*Key::from_slice(key)),
+        Secret::Passphrase(passphrase) => {
+            let mut key = [0u8; 32];
+            Argon2::default()
+                .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                .map_err(|e| anyhow!("failed to derive a key from the passphrase: {}", e))?;
+            Ok(*Key::from_slice(&key))
+        }
+    }
+}
+
+fn encrypt(plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let ciphertext = XChaCha20Poly1305::new(&key(&salt)?)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow!("failed to encrypt"))?;
+
+    Ok([MAGIC, &salt[..], nonce.as_slice(), &ciphertext[..]].concat())
+}
+
+fn decrypt(path: &Path, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let data = &data[MAGIC.len()..];
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(anyhow!("{} is truncated", path.display()));
+    }
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    XChaCha20Poly1305::new(&key(salt)?)
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            anyhow!(
+                "couldn't decrypt {}, the key or passphrase doesn't match",
+                path.display()
+            )
+        })
+}
+
+/// reads a file written by `write`, decrypting it if needed; None when the
+/// file doesn't exist
+pub fn read_to_string(path: &Path) -> anyhow::Result<Option<String>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+
+    let data = if data.starts_with(MAGIC) {
+        decrypt(path, &data)?
+    } else {
+        data
+    };
+
+    String::from_utf8(data)
+        .map(Some)
+        .with_context(|| format!("{} isn't valid utf-8", path.display()))
+}
+
+/// writes a file that only the current user can read, encrypted when
+/// `[encryption]` is enabled. it's written to a temporary file next to it
+/// first and renamed over it, so a crash can't leave it half written
+pub fn write(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let data = if settings().enabled {
+        encrypt(contents.as_bytes())?
+    } else {
+        contents.as_bytes().to_vec()
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = path.with_file_name(format!(
+        ".{}.{:08x}.tmp",
+        path.file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        OsRng.next_u32()
+    ));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let written = options
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(&data)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e).with_context(|| format!("failed to write {}", path.display()));
+    }
+
+    Ok(())
+}
+
+/// appends a line to a file read with `read_to_string`. an encrypted file
+/// can't be appended to, so when it is or is to be, it's rewritten with the
+/// line added by `write`
+pub fn append_line(path: &Path, line: &str) -> anyhow::Result<()> {
+    let encrypted = fs::File::open(path)
+        .and_then(|mut file| {
+            let mut magic = vec![0; MAGIC.len()];
+            file.read_exact(&mut magic).map(|_| magic == MAGIC)
+        })
+        .unwrap_or(false);
+    if settings().enabled || encrypted {
+        let mut contents = read_to_string(path)?.unwrap_or_default();
+        contents.push_str(line);
+        contents.push('\n');
+        return write(path, &contents);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.create(true).append(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> anyhow::Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    if hex.len() != key.len() * 2 {
+        return Err(anyhow!("the encryption key in the keychain is malformed"));
+    }
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .context("the encryption key in the keychain is malformed")?;
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_a_file_without_leaving_anything_behind() {
+        let dir = env::temp_dir().join(format!("clockhand-write-{:08x}", OsRng.next_u32()));
+        let path = dir.join("state.json");
+
+        write(&path, "old").unwrap();
+        write(&path, "new").unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap().as_deref(), Some("new"));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
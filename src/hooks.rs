@@ -0,0 +1,95 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use chrono::Local;
+use serde_json::{json, Value};
+
+use crate::{config::HookSettings, logging, project::Project};
+
+/// things `watch` does that can run a hook
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    /// activity in a project without a timer running for it
+    UntrackedActivity,
+    TimerStarted,
+    TimerStopped,
+    ReminderSent,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::UntrackedActivity => "untracked_activity",
+            Event::TimerStarted => "timer_started",
+            Event::TimerStopped => "timer_stopped",
+            Event::ReminderSent => "reminder_sent",
+        }
+    }
+
+    fn command(self, hooks: &HookSettings) -> Option<&str> {
+        match self {
+            Event::UntrackedActivity => hooks.on_untracked_activity.as_deref(),
+            Event::TimerStarted => hooks.on_timer_started.as_deref(),
+            Event::TimerStopped => hooks.on_timer_stopped.as_deref(),
+            Event::ReminderSent => hooks.on_reminder_sent.as_deref(),
+        }
+    }
+}
+
+/// runs the hook for an event, if one's configured, with the shell, passing
+/// json about the event and project on stdin, merged with `details`. the hook
+/// runs in the background and a failing one is only logged
+pub fn run(hooks: &HookSettings, event: Event, project: &Project, details: Value) {
+    let Some(command) = event.command(hooks) else {
+        return;
+    };
+
+    let mut payload = json!({
+        "event": event.name(),
+        "at": Local::now().to_rfc3339(),
+        "project": {
+            "name": project.name,
+            "harvest_project_id": project.harvest_project_id,
+            "root": project.root,
+            "branch": project.git_branch(),
+        },
+    });
+    if let (Some(payload), Value::Object(details)) = (payload.as_object_mut(), details) {
+        payload.extend(details);
+    }
+
+    let shell = if cfg!(windows) {
+        ["cmd", "/C"]
+    } else {
+        ["sh", "-c"]
+    };
+    let child = Command::new(shell[0])
+        .args([shell[1], command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            logging::warn!("couldn't run the {} hook: {}", event.name(), e);
+            return;
+        }
+    };
+
+    let name = event.name();
+    std::thread::spawn(move || {
+        if let Some(mut stdin) = child.stdin.take() {
+            // a hook that doesn't read its input closes the pipe early
+            let _ = writeln!(stdin, "{}", payload);
+        }
+        match child.wait() {
+            Ok(status) if !status.success() => {
+                logging::warn!("the {} hook failed: {}", name, status)
+            }
+            Ok(_) => {}
+            Err(e) => logging::warn!("couldn't wait on the {} hook: {}", name, e),
+        }
+    });
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+/// how long since the last keyboard or mouse input, when the platform can
+/// tell. async as linux asks xprintidle, which watch's loop can't wait on
+pub async fn system_idle_time() -> Option<Duration> {
+    #[cfg(target_os = "macos")]
+    {
+        #[link(name = "ApplicationServices", kind = "framework")]
+        extern "C" {
+            fn CGEventSourceSecondsSinceLastEventType(state_id: i32, event_type: u32) -> f64;
+        }
+        // kCGEventSourceStateCombinedSessionState and kCGAnyInputEventType
+        let seconds = unsafe { CGEventSourceSecondsSinceLastEventType(0, u32::MAX) };
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+
+    #[cfg(windows)]
+    {
+        #[repr(C)]
+        struct LastInputInfo {
+            size: u32,
+            time: u32,
+        }
+        #[link(name = "user32")]
+        extern "system" {
+            fn GetLastInputInfo(info: *mut LastInputInfo) -> i32;
+        }
+        #[link(name = "kernel32")]
+        extern "system" {
+            fn GetTickCount() -> u32;
+        }
+
+        let mut info = LastInputInfo {
+            size: std::mem::size_of::<LastInputInfo>() as u32,
+            time: 0,
+        };
+        if unsafe { GetLastInputInfo(&mut info) } == 0 {
+            return None;
+        }
+        // both are milliseconds since boot, wrapping every 49 days
+        let milliseconds = unsafe { GetTickCount() }.wrapping_sub(info.time);
+        Some(Duration::from_millis(milliseconds as u64))
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        // xprintidle prints milliseconds since the last input on X11
+        let output = tokio::process::Command::new("xprintidle")
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let milliseconds: u64 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .ok()?;
+        Some(Duration::from_millis(milliseconds))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        None
+    }
+}
+
+/// whether the screen is locked, as far as the platform can tell: from the
+/// session ioreg reports on macOS, and logind's LockedHint elsewhere
+pub async fn screen_locked() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        let Ok(output) = tokio::process::Command::new("ioreg")
+            .args(["-n", "Root", "-d1"])
+            .output()
+            .await
+        else {
+            return false;
+        };
+        String::from_utf8_lossy(&output.stdout).contains("\"CGSSessionScreenIsLocked\"=Yes")
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+        let Ok(output) = tokio::process::Command::new("loginctl")
+            .args(["show-session", &session, "--property=LockedHint", "--value"])
+            .output()
+            .await
+        else {
+            return false;
+        };
+        String::from_utf8_lossy(&output.stdout).trim() == "yes"
+    }
+
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
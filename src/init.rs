@@ -0,0 +1,92 @@
+use std::{env, fs, path::Path};
+
+use anyhow::{anyhow, Context};
+
+use crate::{
+    auth,
+    config::Settings,
+    migrate::PROJECT_CONFIG_VERSION,
+    project::ProjectConfig,
+    prompt::{confirm, prompt},
+};
+
+/// walks through logging in, picking a harvest project and writing a
+/// clockhand.json for the current directory
+pub async fn init(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> anyhow::Result<()> {
+    let path = env::current_dir()?.join(concat!(env!("CARGO_PKG_NAME"), ".json"));
+    if path.exists() && !confirm(&format!("{} already exists, replace it?", path.display()))? {
+        return Ok(());
+    }
+
+    let token_data = match auth::validated_credentials(config_dir, settings, account).await {
+        Ok(token_data) => token_data,
+        Err(e) => {
+            println!("{:#}\n", e);
+            auth::login(config_dir, account).await?;
+            auth::validated_credentials(config_dir, settings, account).await?
+        }
+    };
+
+    let mut assignments = token_data.tracker().project_assignments().await?;
+    if assignments.is_empty() {
+        return Err(anyhow!(
+            "you aren't assigned to any active harvest projects"
+        ));
+    }
+    assignments
+        .sort_by(|a, b| (&a.client_name, &a.project_name).cmp(&(&b.client_name, &b.project_name)));
+
+    println!();
+    for (i, assignment) in assignments.iter().enumerate() {
+        println!(
+            "{:>3}  {} — {}",
+            i + 1,
+            assignment.client_name.as_deref().unwrap_or("(no client)"),
+            assignment.project_name
+        );
+    }
+    println!();
+
+    let choice = prompt("Project number: ")?
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| assignments.get(i))
+        .ok_or_else(|| anyhow!("pick one of the numbers listed"))?;
+
+    let name = prompt(&format!("Name [{}]: ", choice.project_name))?;
+    let project_config = ProjectConfig {
+        version: PROJECT_CONFIG_VERSION,
+        harvest_project_id: choice.project_id,
+        name: if name.is_empty() {
+            choice.project_name.clone()
+        } else {
+            name
+        },
+        account: account.map(|a| a.to_string()),
+        rounding: None,
+        task: None,
+        billable: None,
+        auto_start: false,
+        daily_target: None,
+        ignore: Vec::new(),
+        only: Vec::new(),
+        interval: None,
+        sound: None,
+        urgency: None,
+        escalation: Vec::new(),
+        apps: Vec::new(),
+        window_titles: Vec::new(),
+        templates: Default::default(),
+    };
+
+    fs::write(&path, serde_json::to_string_pretty(&project_config)? + "\n")
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+
+use crate::{
+    auth, config::Settings, decimal_hours_to_string, prompt, report::format_amount,
+    strip_newlines_and_tabs, tracker::Named,
+};
+
+/// drafts a harvest invoice for a client's uninvoiced billable time up to
+/// `through`, after listing it per project and confirming
+pub async fn invoice(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    client: &str,
+    through: NaiveDate,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let tracker = auth::validated_credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+
+    let clients = tracker.clients().await?;
+    let client = match_client(&clients, client)?;
+    let client_id = client.id;
+    let client_name = client.name.clone();
+
+    let entries = tracker.uninvoiced_time_entries(client_id, through).await?;
+    if entries.is_empty() {
+        return Err(anyhow!(
+            "{} has no uninvoiced billable time through {}",
+            client_name,
+            through
+        ));
+    }
+
+    // (project id, name, hours, amount), in the order harvest listed them
+    let mut projects: Vec<(i32, String, f32, f32)> = Vec::new();
+    for entry in entries.iter() {
+        let project_id = entry.project.id;
+        let hours = entry.hours;
+        let amount = hours * entry.billable_rate.unwrap_or_default();
+        match projects.iter_mut().find(|(id, _, _, _)| *id == project_id) {
+            Some((_, _, total, total_amount)) => {
+                *total += hours;
+                *total_amount += amount;
+            }
+            None => projects.push((project_id, entry.project.name.clone(), hours, amount)),
+        }
+    }
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout());
+    for (_, name, hours, amount) in projects.iter() {
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}",
+            strip_newlines_and_tabs(name),
+            decimal_hours_to_string(*hours),
+            format_amount(settings, *amount)
+        )?;
+    }
+    writeln!(
+        &mut tw,
+        "Total\t{}\t{}",
+        decimal_hours_to_string(projects.iter().map(|(_, _, hours, _)| hours).sum()),
+        format_amount(
+            settings,
+            projects.iter().map(|(_, _, _, amount)| amount).sum()
+        )
+    )?;
+    tw.flush()?;
+
+    if !yes
+        && !prompt::confirm(&format!(
+            "Draft an invoice for {} with this time?",
+            client_name
+        ))?
+    {
+        return Ok(());
+    }
+
+    let project_ids = projects.iter().map(|(id, _, _, _)| *id).collect();
+    let invoice = tracker
+        .draft_invoice(client_id, project_ids, through, settings.today())
+        .await?;
+    println!(
+        "Drafted invoice {} for {}: {}",
+        invoice.number, client_name, invoice.url
+    );
+
+    Ok(())
+}
+
+/// the client named `query` ignoring case, or else the best fuzzy match, or
+/// the one with that harvest id
+fn match_client<'a>(clients: &'a [Named], query: &str) -> anyhow::Result<&'a Named> {
+    if let Some(client) = clients
+        .iter()
+        .find(|c| c.id.to_string() == query || c.name.to_lowercase() == query.to_lowercase())
+    {
+        return Ok(client);
+    }
+
+    let matcher = SkimMatcherV2::default();
+    clients
+        .iter()
+        .filter_map(|c| Some((matcher.fuzzy_match(&c.name, query)?, c)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, c)| c)
+        .ok_or_else(|| anyhow!("no client matches {:?}", query))
+}
@@ -0,0 +1,211 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Seek, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Settings, theme};
+
+/// size at which the log is rotated
+const MAX_SIZE: u64 = 1024 * 1024;
+
+/// rotated logs kept, as watch.log.1 (the newest) and so on
+const KEEP: usize = 3;
+
+/// the log file lines are written to as well as stdout, once `init` is called
+static LOG: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+/// a line of the log, stored as json
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Line {
+    pub at: DateTime<Local>,
+    pub level: Level,
+    pub message: String,
+}
+
+/// logs a line to stdout and the log file
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Info, &format!($($arg)*))
+    };
+}
+
+/// logs a line about something that went wrong but didn't stop watch,
+/// exported as `warn`, which the built-in attribute keeps it from being named
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Warn, &format!($($arg)*))
+    };
+}
+
+/// logs a line about watch itself failing
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Error, &format!($($arg)*))
+    };
+}
+
+pub(crate) use error;
+pub(crate) use info;
+pub(crate) use warning as warn;
+
+/// clockhand in the platform's state directory, or its local data directory
+/// where there's no such thing
+pub fn log_dir() -> anyhow::Result<PathBuf> {
+    let dir = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .ok_or_else(|| anyhow!("couldn't determine a directory for logs"))?;
+    Ok(dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// the log written by `watch`
+pub fn log_path() -> anyhow::Result<PathBuf> {
+    Ok(log_dir()?.join("watch.log"))
+}
+
+/// starts writing lines to the log file too
+pub fn init() -> anyhow::Result<()> {
+    let path = log_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    *LOG.lock().unwrap() = Some(path);
+    Ok(())
+}
+
+/// prints a line and appends it to the log file, rotating it when it's grown
+/// too big. a log that can't be written doesn't stop anything
+pub fn write(level: Level, message: &str) {
+    match level {
+        Level::Info => println!("{}", message),
+        Level::Warn | Level::Error => eprintln!("{}", message),
+    }
+
+    let log = LOG.lock().unwrap();
+    let Some(path) = log.as_ref() else {
+        return;
+    };
+    let line = Line {
+        at: Local::now(),
+        level,
+        message: message.to_string(),
+    };
+    if let Err(e) = append(path, &line) {
+        eprintln!("couldn't write to {}: {:#}", path.display(), e);
+    }
+}
+
+fn append(path: &Path, line: &Line) -> anyhow::Result<()> {
+    if fs::metadata(path).is_ok_and(|m| m.len() >= MAX_SIZE) {
+        rotate(path)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(line)?)?;
+    Ok(())
+}
+
+/// shifts watch.log to watch.log.1, watch.log.1 to watch.log.2 and so on,
+/// dropping the oldest
+fn rotate(path: &Path) -> anyhow::Result<()> {
+    let rotated = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    for n in (1..KEEP).rev() {
+        if rotated(n).exists() {
+            fs::rename(rotated(n), rotated(n + 1))?;
+        }
+    }
+    fs::rename(path, rotated(1))?;
+    Ok(())
+}
+
+/// prints the last `lines` lines of the log, then new ones as they're written
+/// when following
+pub fn logs(settings: &Settings, lines: usize, follow: bool) -> anyhow::Result<()> {
+    let path = log_path()?;
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(_) if !follow => {
+            println!("there's no log yet, watch writes one to {}", path.display());
+            return Ok(());
+        }
+        Err(_) => wait_for(&path),
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut recent: Vec<String> = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        recent.push(std::mem::take(&mut line));
+    }
+    for line in recent.iter().skip(recent.len().saturating_sub(lines)) {
+        print_line(settings, line);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut position = reader.stream_position()?;
+    loop {
+        match reader.read_line(&mut line)? {
+            0 => {
+                std::thread::sleep(Duration::from_millis(500));
+                // rotated, start on the new file
+                if fs::metadata(&path).map_or(true, |m| m.len() < position) {
+                    reader = BufReader::new(wait_for(&path));
+                    position = 0;
+                }
+            }
+            read => {
+                position += read as u64;
+                print_line(settings, &std::mem::take(&mut line));
+            }
+        }
+    }
+}
+
+/// opens a log that doesn't exist yet once it does
+fn wait_for(path: &Path) -> File {
+    loop {
+        if let Ok(file) = File::open(path) {
+            return file;
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// a log line as `date time message`, warnings in yellow and errors in red,
+/// or as it is when it isn't json
+fn print_line(settings: &Settings, line: &str) {
+    let Ok(parsed) = serde_json::from_str::<Line>(line) else {
+        print!("{}", line);
+        return;
+    };
+    let message = match parsed.level {
+        Level::Info => parsed.message.normal(),
+        Level::Warn => parsed.message.yellow(),
+        Level::Error => parsed.message.red(),
+    };
+    println!(
+        "{} {}",
+        theme::date(
+            &settings.theme,
+            &parsed.at.format("%Y-%m-%d %H:%M:%S").to_string()
+        ),
+        message
+    );
+}
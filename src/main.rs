@@ -1,26 +1,52 @@
-use std::time::{Duration, Instant};
-use std::{
-    env, fs,
-    io::{self, Write},
-    path::{Path, PathBuf},
-};
-
-use anyhow::{anyhow, Context};
-use chrono::{Local, NaiveDate, Weekday};
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod activity;
+mod api;
+mod apps;
+mod auth;
+mod cache;
+mod calendar;
+mod config;
+mod control;
+mod day_summary;
+mod doctor;
+mod encryption;
+mod hooks;
+mod idle;
+mod init;
+mod invoice;
+mod logging;
+mod menubar;
+mod migrate;
+mod notification;
+mod pomodoro;
+mod project;
+mod prompt;
+mod rate_limit;
+mod reconcile;
+mod report;
+mod secrets;
+mod service;
+mod shell;
+mod snooze;
+mod start_reminder;
+mod state;
+mod targets;
+mod template;
+mod theme;
+mod timer;
+mod tracker;
+mod validate;
+mod watch;
+
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveTime, Weekday};
 use clap::{Args, Parser, Subcommand};
-use indoc::indoc;
-use mac_notification_sys::{
-    error::NotificationResult, get_bundle_identifier_or_default, set_application,
-};
-use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use notify_rust::Notification;
-use serde::Deserialize;
 
 use chrono::Datelike;
-use harvest_openapi::apis::{
-    configuration::{ApiKey, Configuration},
-    default_api::{self as harvest, ListTimeEntriesParams},
-};
+
+use crate::config::Settings;
 
 // use notify::{Config, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 // use std::sync::mpsc::{channel, Receiver};
@@ -31,194 +57,770 @@ use harvest_openapi::apis::{
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// named harvest account to use, defaults to the one set with `auth use`
+    #[arg(long, global = true)]
+    account: Option<String>,
+
+    /// directory holding credentials and settings, defaults to
+    /// ~/.config/clockhand or the platform's config directory
+    #[arg(long, global = true)]
+    config_dir: Option<PathBuf>,
+
+    /// named profile from clockhand.toml whose settings override the defaults
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// prints without colors, as does setting NO_COLOR or piping the output
+    #[arg(long, global = true)]
+    no_color: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// prints timers for the most recent two weeks
-    Report {},
+    /// prints timers for today, this week or this month, or this week and last
+    /// week when no period is given
+    Report(report::ReportArgs),
 
     Watch(WatchArgs),
 
     TestNotification {},
+
+    /// manages harvest credentials
+    Auth(AuthArgs),
+
+    /// prints the harvest user and accounts the credentials belong to
+    Whoami {},
+
+    /// sets up credentials and a clockhand.json for the current directory
+    Init {},
+
+    /// inspects clockhand's configuration
+    Config(ConfigArgs),
+
+    /// checks notifications, network, the file watcher and configuration
+    Doctor {},
+
+    /// starts a harvest timer for the project in the current directory
+    Start {
+        /// alias from clockhand.toml to start a timer for
+        alias: Option<String>,
+
+        /// task name or id, picked interactively when the project has more
+        /// than one task
+        #[arg(long)]
+        task: Option<String>,
+
+        #[command(flatten)]
+        billable: BillableArgs,
+
+        /// notes for the time entry
+        #[arg(long)]
+        notes: Option<String>,
+
+        /// backdates the timer for time already spent, e.g. 20m or 1h15m
+        #[arg(long, value_parser = timer::parse_hours)]
+        ago: Option<f32>,
+    },
+
+    /// stops the running timer, applying the project's rounding
+    Stop {},
+
+    /// restarts the most recent of today's timers
+    Resume {},
+
+    /// ends the running timer at an earlier time and starts one for another
+    /// project from then
+    Split {
+        /// time the other project's work started, e.g. 14:30 or 2:30pm
+        #[arg(long, value_parser = timer::parse_time)]
+        at: NaiveTime,
+
+        /// harvest project name or id, defaults to the project in the current
+        /// directory
+        #[arg(long)]
+        to: Option<String>,
+
+        /// task name or id, picked interactively when the project has more
+        /// than one task
+        #[arg(long)]
+        task: Option<String>,
+    },
+
+    /// prints the running timer and today's total
+    Status {},
+
+    /// drafts a harvest invoice from a client's uninvoiced billable time
+    Invoice {
+        /// client name, fuzzy matched, or harvest id
+        #[arg(long)]
+        client: String,
+
+        /// last day of time to include, defaults to today
+        #[arg(long)]
+        through: Option<NaiveDate>,
+
+        /// drafts without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// alternates work and breaks, pausing the harvest timer during breaks
+    Pomodoro {
+        /// alias from clockhand.toml to start a timer for
+        alias: Option<String>,
+
+        /// minutes of work
+        #[arg(long, default_value = "25")]
+        work: u64,
+
+        /// minutes of break
+        #[arg(long = "break", default_value = "5")]
+        break_minutes: u64,
+
+        /// task name or id, picked interactively when the project has more
+        /// than one task
+        #[arg(long)]
+        task: Option<String>,
+    },
+
+    /// prints what `watch` has logged
+    Logs {
+        /// how many of the most recent lines to print
+        #[arg(short = 'n', long, default_value = "50")]
+        lines: usize,
+
+        /// keeps printing lines as they're logged
+        #[arg(short, long)]
+        follow: bool,
+    },
+
+    /// prints a hook to eval in your shell's startup file, so commands run in
+    /// a project count as activity for `watch`
+    ShellHook {
+        #[arg(value_enum)]
+        shell: shell::Shell,
+    },
+
+    /// prints the running timer and a menu to stop, start or switch timers,
+    /// as an xbar or SwiftBar plugin for the macOS menu bar
+    Menubar {},
+
+    /// offers to log the activity `watch` saw on a day without a timer
+    Reconcile {
+        /// day to reconcile, defaults to today
+        #[arg(long)]
+        date: Option<NaiveDate>,
+
+        /// minutes between bits of activity that still make them one block
+        #[arg(long, default_value = "15")]
+        gap: u64,
+
+        /// logs every untracked block without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// holds off `watch` reminders for a project
+    Snooze {
+        /// harvest project name or id, defaults to the project in the current
+        /// directory
+        project: Option<String>,
+
+        /// how long, e.g. 2h, 90m or 1:30
+        #[arg(long = "for", value_parser = timer::parse_hours, default_value = "30m")]
+        duration: f32,
+
+        /// lets reminders resume now
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// deletes a time entry after confirming
+    Delete {
+        /// index of one of today's entries, as listed by `edit`, or a harvest
+        /// time entry id
+        entry: String,
+
+        /// deletes without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// changes one of today's time entries, prompting for anything not passed
+    Edit {
+        /// index of the entry in the list printed without it, or its harvest
+        /// id
+        entry: Option<String>,
+
+        /// new duration, e.g. 1.5h, 90m, 1h30m or 1:30
+        #[arg(long, value_parser = timer::parse_hours)]
+        hours: Option<f32>,
+
+        /// new notes, replacing the current ones
+        #[arg(long)]
+        notes: Option<String>,
+
+        /// harvest project name or id to move the entry to
+        #[arg(long)]
+        project: Option<String>,
+
+        /// task name or id
+        #[arg(long)]
+        task: Option<String>,
+    },
+
+    /// records time already spent, without running a timer
+    Log {
+        /// how long, e.g. 1.5h, 90m, 1h30m or 1:30, or an alias from
+        /// clockhand.toml
+        hours_or_alias: String,
+
+        /// how long, when an alias is given without a duration of its own
+        #[arg(value_parser = timer::parse_hours)]
+        hours: Option<f32>,
+
+        /// day the time was spent, defaults to today
+        #[arg(long)]
+        date: Option<NaiveDate>,
+
+        /// harvest project name or id, defaults to the project in the current
+        /// directory
+        #[arg(long)]
+        project: Option<String>,
+
+        /// task name or id, picked interactively when the project has more
+        /// than one task
+        #[arg(long)]
+        task: Option<String>,
+
+        #[command(flatten)]
+        billable: BillableArgs,
+
+        /// notes for the time entry
+        #[arg(long)]
+        notes: Option<String>,
+    },
+
+    /// appends a line to the running timer's notes
+    Note {
+        /// text to add
+        text: String,
+    },
+
+    /// stops the running timer and starts one for the project in the current
+    /// directory
+    Switch {
+        /// harvest project name or id to switch to instead
+        project: Option<String>,
+
+        /// task name or id, picked interactively when the project has more
+        /// than one task
+        #[arg(long)]
+        task: Option<String>,
+
+        /// carries the running timer's notes over to the new one
+        #[arg(long)]
+        keep_notes: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct BillableArgs {
+    /// only considers billable tasks, as harvest decides billing by task
+    #[arg(long, conflicts_with = "non_billable")]
+    billable: bool,
+
+    /// only considers non-billable tasks
+    #[arg(long)]
+    non_billable: bool,
+}
+
+impl BillableArgs {
+    fn value(&self) -> Option<bool> {
+        match (self.billable, self.non_billable) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommands,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// checks clockhand.toml, credentials and project configs for problems
+    Validate {
+        /// project clockhand files to check, defaults to `projects` in the
+        /// [watch] section of clockhand.toml
+        project_config_paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+pub struct AuthArgs {
+    #[command(subcommand)]
+    command: AuthCommands,
+}
+
+#[derive(Subcommand)]
+enum AuthCommands {
+    /// prompts for a personal access token and writes the credentials file
+    Login {},
+
+    /// authorizes clockhand through harvest's oauth2 consent page
+    Oauth {
+        /// client id of a harvest oauth2 application
+        #[arg(long)]
+        client_id: String,
+
+        /// client secret of the harvest oauth2 application
+        #[arg(long)]
+        client_secret: String,
+
+        /// local port that receives the redirect
+        #[arg(long, default_value = "8765")]
+        port: u16,
+    },
+
+    /// moves plaintext credential files into the system keychain
+    MigrateKeychain {},
+
+    /// checks the credentials and prints who they belong to
+    Status {},
+
+    /// sets the account used when --account isn't passed
+    Use {
+        /// name of an account logged in with `--account <name> auth login`
+        name: String,
+    },
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct WatchArgs {
+    #[command(subcommand)]
+    command: Option<WatchCommands>,
+
     /// Project clockhand files to watch{n}
     /// example values:{n}
     /// - project_a/clockhand.json project_b/clockhand.json{n}
-    /// - ~/code/*/clockhand.json (shell expansion)
-    // #[arg(short, long)]
-    // files: Vec<String>,
+    /// - ~/code/*/clockhand.json (shell expansion){n}
+    /// - ~/code (searched for clockhand files, including new ones)
+    ///
+    /// defaults to `projects` in the [watch] section of clockhand.toml
     #[clap(name = "project-config-paths")]
     project_config_paths: Vec<String>,
 
-    /// interval in seconds that the notifications will occur at
-    #[arg(short, long, default_value = "60")]
-    interval: u64,
+    /// interval in seconds that the notifications will occur at, defaults to
+    /// `interval` in clockhand.toml or 60
+    #[arg(short, long)]
+    interval: Option<u64>,
+
+    /// on macOS, counts using the apps or windows listed in a project's
+    /// `apps` and `window_titles` as activity on it
+    #[arg(long)]
+    track_apps: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct AccessTokenConfig {
-    token: String,
-    account_id: i32,
+#[derive(Subcommand)]
+enum WatchCommands {
+    /// runs watch at login as a launchd agent, or a systemd user service,
+    /// with the projects in clockhand.toml
+    Install {
+        /// interval in seconds that the notifications will occur at
+        #[arg(short, long)]
+        interval: Option<u64>,
+
+        /// writes and enables a systemd user unit instead, on linux
+        #[arg(long)]
+        systemd: bool,
+    },
+
+    /// stops the agent set up by `watch install` and removes it
+    Uninstall {},
+
+    /// prints what the running watch is watching, when each project last
+    /// changed, snoozes and the last notification
+    Status {},
+
+    /// stops the running watch notifying or starting timers until `resume`
+    Pause {},
+
+    /// lets the running watch notify again after `pause`
+    Resume {},
+
+    /// tells the running watch there's activity in a directory, as the shell
+    /// hook does for each command
+    Activity {
+        /// defaults to the current directory
+        path: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    theme::configure(cli.no_color);
+    let config_dir = config::config_dir(cli.config_dir.as_deref())?;
+    // `config validate` and `doctor` report problems in clockhand.toml itself
+    let settings = match Settings::load(&config_dir, cli.profile.as_deref()) {
+        Err(_) if matches!(cli.command, Some(Commands::Config(_) | Commands::Doctor {})) => {
+            Settings::default()
+        }
+        settings => settings?,
+    };
+    encryption::configure(&settings.encryption);
+    let account = auth::resolve_account(&config_dir, &settings, cli.account.as_deref())?;
 
     match &cli.command {
+        Some(Commands::Watch(WatchArgs {
+            command: Some(WatchCommands::Install { interval, systemd }),
+            ..
+        })) => {
+            service::install(
+                &config_dir,
+                cli.account.as_deref(),
+                cli.profile.as_deref(),
+                *interval,
+                *systemd,
+            )?;
+        }
+        Some(Commands::Watch(WatchArgs {
+            command: Some(WatchCommands::Uninstall {}),
+            ..
+        })) => {
+            service::uninstall()?;
+        }
+        Some(Commands::Watch(WatchArgs {
+            command: Some(WatchCommands::Status {}),
+            ..
+        })) => {
+            let status = control::send(&config_dir, control::Request::Status)?;
+            control::print_status(&settings, &status)?;
+        }
+        Some(Commands::Watch(WatchArgs {
+            command: Some(WatchCommands::Pause {}),
+            ..
+        })) => {
+            control::send(&config_dir, control::Request::Pause)?;
+            println!("Paused watch, `watch resume` turns notifications back on");
+        }
+        Some(Commands::Watch(WatchArgs {
+            command: Some(WatchCommands::Resume {}),
+            ..
+        })) => {
+            control::send(&config_dir, control::Request::Resume)?;
+            println!("Resumed watch");
+        }
+        Some(Commands::Watch(WatchArgs {
+            command: Some(WatchCommands::Activity { path }),
+            ..
+        })) => {
+            let path = match path {
+                Some(path) => path.clone(),
+                None => std::env::current_dir()?,
+            };
+            // watched roots are canonical, $PWD may go through a symlink
+            let path = dunce::canonicalize(&path)
+                .with_context(|| format!("failed to resolve {}", path.display()))?;
+            control::send(&config_dir, control::Request::Activity { path })?;
+        }
         Some(Commands::Watch(watch_args)) => {
-            setup_notification_application()?;
-
-            let token_data = access_token()?;
-
-            let config = Configuration {
-                bearer_access_token: Some(token_data.token),
-                api_key: Some(ApiKey {
-                    key: token_data.account_id.to_string(),
-                    prefix: None,
-                }),
-                ..Default::default()
+            notification::setup()?;
+            logging::init()?;
+
+            let interval = Duration::from_secs(
+                watch_args
+                    .interval
+                    .or(settings.watch.interval)
+                    .unwrap_or(60),
+            );
+            let project_config_paths = if watch_args.project_config_paths.is_empty() {
+                config::expand_paths(&settings.watch.projects)?
+            } else {
+                watch_args
+                    .project_config_paths
+                    .iter()
+                    .map(PathBuf::from)
+                    .collect()
             };
 
-            // Create a channel to receive file system events
-            let (tx, rx) = std::sync::mpsc::channel();
-
-            // Create a watcher object and register the directories to watch
-
-            let mut watcher: RecommendedWatcher = Watcher::new(
-                tx,
-                Config::default().with_poll_interval(Duration::from_secs(2)),
+            watch::watch(
+                &config_dir,
+                &settings,
+                account,
+                &project_config_paths,
+                interval,
+                watch_args.track_apps || settings.watch.track_apps,
             )
-            .unwrap();
-
-            let mut last_request_time = Instant::now()
-                .checked_sub(Duration::from_secs(watch_args.interval))
-                .unwrap();
-
-            let mut projects: Vec<Project> = Vec::new();
-
-            for clockhand_config_path in watch_args.project_config_paths.iter() {
-                let clockhand_config_pathbuf = PathBuf::from(clockhand_config_path);
-                let project = read_project_config(&clockhand_config_pathbuf)?;
-
-                watcher
-                    .watch(&project.root, RecursiveMode::Recursive)
-                    .unwrap();
-
-                println!("Watching {:?}", &project.root);
-                projects.push(project);
-            }
-
-            // Start an event loop to process file system events
-            loop {
-                match rx.recv() {
-                    Ok(e) => match e {
-                        Ok(ee) => {
-                            // println!("changed: {:?}", &ee);
-                            // println!("info: {:?}", &ee.info());
-                            // debouncer.put(String::from(ee.paths[0]))
-
-                            let path = ee.paths.first();
-                            println!(
-                                "changed: {:?}, time since {:?}",
-                                path,
-                                last_request_time.elapsed()
-                            );
-
-                            if last_request_time.elapsed()
-                                > Duration::from_secs(watch_args.interval)
-                            {
-                                println!("notifying!");
-                                last_request_time = Instant::now();
-                                notify_project_timer_status(path.unwrap(), &projects, &config)
-                                    .await?;
-                            } else {
-                                println!("interval hasn't passed, not notifying");
-                            }
-                        }
-                        Err(ee) => {
-                            println!("watch error: {:?}", ee);
-                        }
-                    },
-                    Err(e) => println!("watch error: {:?}", e),
-                }
-            }
+            .await?;
         }
         Some(Commands::TestNotification {}) => {
-            setup_notification_application()?;
-
-            Notification::new()
-                .summary(concat!("Test notification from ", env!("CARGO_PKG_NAME")))
-                .body(&format!(
+            notification::setup()?;
+            notification::show(
+                concat!("Test notification from ", env!("CARGO_PKG_NAME")),
+                &format!(
                     "This is a test notification at {}",
                     chrono::Local::now().to_rfc2822()
-                ))
-                .sound_name("Sosumi")
-                .show()?;
+                ),
+                &settings.notifications.sound,
+            )?;
         }
 
-        Some(Commands::Report {}) => {
-            let token_data = access_token()?;
-
-            let config = Configuration {
-                bearer_access_token: Some(token_data.token),
-                api_key: Some(ApiKey {
-                    key: token_data.account_id.to_string(),
-                    prefix: None,
-                }),
+        Some(Commands::Report(report_args)) => {
+            report::report(&config_dir, &settings, account.as_deref(), report_args).await?;
+        }
+        Some(Commands::Auth(auth_args)) => match auth_args.command {
+            AuthCommands::Login {} => auth::login(&config_dir, account.as_deref()).await?,
+            AuthCommands::Oauth {
+                ref client_id,
+                ref client_secret,
+                port,
+            } => {
+                auth::oauth(
+                    &config_dir,
+                    account.as_deref(),
+                    client_id,
+                    client_secret,
+                    port,
+                )
+                .await?
+            }
+            AuthCommands::MigrateKeychain {} => auth::migrate_to_keychain(&config_dir)?,
+            AuthCommands::Use { ref name } => auth::use_account(&config_dir, name)?,
+            AuthCommands::Status {} => {
+                auth::status(&auth::credentials(&config_dir, &settings, account.as_deref()).await?)
+                    .await?
+            }
+        },
+        Some(Commands::Whoami {}) => {
+            auth::status(&auth::credentials(&config_dir, &settings, account.as_deref()).await?)
+                .await?
+        }
+        Some(Commands::Config(config_args)) => match &config_args.command {
+            ConfigCommands::Validate {
+                project_config_paths,
+            } => {
+                let project_config_paths = if project_config_paths.is_empty() {
+                    config::expand_paths(&settings.watch.projects)?
+                } else {
+                    project_config_paths.clone()
+                };
+                validate::validate(
+                    &config_dir,
+                    &settings,
+                    account.as_deref(),
+                    &project_config_paths,
+                )
+                .await?;
+            }
+        },
+        Some(Commands::Doctor {}) => {
+            doctor::doctor(&config_dir, cli.profile.as_deref(), account.as_deref()).await?;
+        }
+        Some(Commands::Start {
+            alias,
+            task,
+            billable,
+            notes,
+            ago,
+        }) => {
+            let target = timer::EntryTarget {
+                task: task.clone(),
+                billable: billable.value(),
+                notes: notes.clone(),
                 ..Default::default()
             };
+            let target = match alias {
+                Some(alias) => target.or_alias(settings.alias(alias)?)?,
+                None => target,
+            };
 
-            let me = harvest::retrieve_the_currently_authenticated_user(&config)
-                .await
-                .unwrap();
-
-            let now = Local::now();
-            let today = now.date_naive();
-
-            // iso week
-            let iso_week = today.iso_week().week();
-
-            // first monday of this week
-            let start_of_week = NaiveDate::from_isoywd_opt(today.year(), iso_week, Weekday::Mon)
-                .ok_or_else(|| anyhow!("could not determine first day of week"))?;
-
-            let start_of_last_week = start_of_week - chrono::Duration::weeks(1);
-
-            let timers = harvest::list_time_entries(
-                &config,
-                ListTimeEntriesParams {
-                    user_id: me.id,
-                    per_page: Some(200),
-                    from: Some(start_of_last_week.to_string()),
+            timer::start(&config_dir, &settings, account.as_deref(), target, *ago).await?;
+        }
+        Some(Commands::Stop {}) => {
+            timer::stop(&config_dir, &settings, account.as_deref()).await?;
+        }
+        Some(Commands::Split { at, to, task }) => {
+            timer::split(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                *at,
+                timer::EntryTarget {
+                    project: to.clone(),
+                    task: task.clone(),
                     ..Default::default()
                 },
             )
-            .await
-            .unwrap();
-
-            // loops through all the timers and prints them using the tabwriter crate
-            let mut tw = tabwriter::TabWriter::new(io::stdout());
-
-            timers.time_entries.into_iter().for_each(|timer| {
-                let proj = timer.project.unwrap();
-                writeln!(
-                    &mut tw,
-                    "{}\t{}\t{}\t{}\t{}",
-                    timer.spent_date.unwrap(),
-                    &proj.id.unwrap(),
-                    strip_newlines_and_tabs(&proj.name.unwrap()),
-                    decimal_hours_to_string(timer.hours.unwrap()),
-                    truncate_with_ellipsis(&timer.notes.unwrap_or("(none)".to_string()), 60)
-                )
-                .unwrap();
-            });
+            .await?;
+        }
+        Some(Commands::Resume {}) => {
+            timer::resume(&config_dir, &settings, account.as_deref()).await?;
+        }
+        Some(Commands::Switch {
+            project,
+            task,
+            keep_notes,
+        }) => {
+            timer::switch(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                project.as_deref(),
+                task.as_deref(),
+                *keep_notes,
+            )
+            .await?;
+        }
+        Some(Commands::Status {}) => {
+            timer::status(&config_dir, &settings, account.as_deref()).await?;
+        }
+        Some(Commands::Note { text }) => {
+            timer::note(&config_dir, &settings, account.as_deref(), text).await?;
+        }
+        Some(Commands::Log {
+            hours_or_alias,
+            hours,
+            date,
+            project,
+            task,
+            billable,
+            notes,
+        }) => {
+            let target = timer::EntryTarget {
+                project: project.clone(),
+                task: task.clone(),
+                billable: billable.value(),
+                notes: notes.clone(),
+                hours: *hours,
+            };
+            let target =
+                match settings.aliases.get(hours_or_alias) {
+                    Some(alias) => target.or_alias(alias)?,
+                    None => timer::EntryTarget {
+                        hours: Some(timer::parse_hours(hours_or_alias).with_context(|| {
+                            format!("{:?} isn't an alias either", hours_or_alias)
+                        })?),
+                        ..target
+                    },
+                };
 
-            tw.flush()?;
+            timer::log(&config_dir, &settings, account.as_deref(), target, *date).await?;
+        }
+        Some(Commands::Invoice {
+            client,
+            through,
+            yes,
+        }) => {
+            invoice::invoice(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                client,
+                through.unwrap_or_else(|| settings.today()),
+                *yes,
+            )
+            .await?;
+        }
+        Some(Commands::Pomodoro {
+            alias,
+            work,
+            break_minutes,
+            task,
+        }) => {
+            notification::setup()?;
+
+            let target = timer::EntryTarget {
+                task: task.clone(),
+                ..Default::default()
+            };
+            let target = match alias {
+                Some(alias) => target.or_alias(settings.alias(alias)?)?,
+                None => target,
+            };
+
+            pomodoro::pomodoro(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                target,
+                *work,
+                *break_minutes,
+            )
+            .await?;
+        }
+        Some(Commands::Edit {
+            entry,
+            hours,
+            notes,
+            project,
+            task,
+        }) => {
+            timer::edit(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                entry.as_deref(),
+                timer::EntryChanges {
+                    hours: *hours,
+                    notes: notes.clone(),
+                    project: project.clone(),
+                    task: task.clone(),
+                },
+            )
+            .await?;
+        }
+        Some(Commands::Logs { lines, follow }) => {
+            logging::logs(&settings, *lines, *follow)?;
+        }
+        Some(Commands::Reconcile { date, gap, yes }) => {
+            reconcile::reconcile(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                date.unwrap_or_else(|| settings.today()),
+                *gap,
+                *yes,
+            )
+            .await?;
+        }
+        Some(Commands::Menubar {}) => {
+            menubar::menubar(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                cli.profile.as_deref(),
+            )
+            .await?;
+        }
+        Some(Commands::ShellHook { shell }) => {
+            shell::hook(&config_dir, *shell)?;
+        }
+        Some(Commands::Snooze {
+            project,
+            duration,
+            clear,
+        }) => {
+            snooze::snooze(
+                &config_dir,
+                &settings,
+                project.as_deref(),
+                *duration,
+                *clear,
+            )?;
+        }
+        Some(Commands::Delete { entry, yes }) => {
+            timer::delete(&config_dir, &settings, account.as_deref(), entry, *yes).await?;
+        }
+        Some(Commands::Init {}) => {
+            init::init(&config_dir, &settings, account.as_deref()).await?;
         }
         None => {}
     }
@@ -226,43 +828,11 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn config_dir() -> anyhow::Result<PathBuf> {
-    let home = env::var("HOME")?;
-    let path = Path::new(&home)
-        .join(".config")
-        .join(env!("CARGO_PKG_NAME"));
-    Ok(path)
-}
-
-fn access_token() -> anyhow::Result<AccessTokenConfig> {
-    let path = Path::new(&config_dir()?).join("access-token.json");
-    let path_string = path
-        .as_os_str()
-        .to_str()
-        .ok_or_else(|| anyhow!("failed to generate a path to access-token"))?;
-
-    let token_file_contents = fs::read_to_string(&path).context(format!(
-        indoc! {r#"
-            didn't find the credentials config file at {}
-
-            1. visit https://id.getharvest.com/developers
-            2. Create new personal access token
-            3. _TODO_ implement command to write json like {{"token": "123...", account_id: 456}}
-               into this file: {}
-        "#},
-        path_string, path_string
-    ))?;
-
-    let token_data = serde_json::from_str::<AccessTokenConfig>(&token_file_contents)
-        .with_context(|| format!("bad format for {}", path_string))?;
-
-    Ok(token_data)
-}
-
-fn setup_notification_application() -> NotificationResult<()> {
-    let app_bundle_id = get_bundle_identifier_or_default("Terminal");
-    set_application(&app_bundle_id)?;
-    Ok(())
+/// the most recent day on or before `day` that falls on `week_start`
+fn start_of_week(day: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let days_since_start =
+        (day.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    day - chrono::Duration::days(days_since_start as i64)
 }
 
 /// removes all newlines and tabs from a string
@@ -272,12 +842,11 @@ fn strip_newlines_and_tabs(s: &str) -> String {
 
 /// truncates a string to a given length based on word boundaries, and appends an ellipsis
 fn truncate_with_ellipsis(s: &str, len: usize) -> String {
-    let mut words = s.split_whitespace();
     let mut truncated = String::new();
 
-    while let Some(word) = words.next() {
+    for word in s.split_whitespace() {
         if truncated.len() + word.len() > len {
-            truncated.push_str("…");
+            truncated.push('…');
             break;
         }
 
@@ -298,156 +867,3 @@ fn decimal_hours_to_string(decimal_hours: f32) -> String {
         format!("{:02}h {:02}m", hours, minutes)
     }
 }
-
-#[derive(Debug, Deserialize)]
-pub struct ProjectConfig {
-    pub harvest_project_id: i32,
-    pub name: String,
-}
-
-struct Project {
-    pub harvest_project_id: i32,
-    pub root: PathBuf,
-    pub name: String,
-}
-
-impl Project {
-    fn contains_file(&self, path: PathBuf) -> bool {
-        path.starts_with(&self.root)
-    }
-}
-
-fn read_project_config(path: &PathBuf) -> anyhow::Result<Project> {
-    let path_string = path
-        .as_os_str()
-        .to_str()
-        .ok_or_else(|| anyhow!("failed to generate a path to project config"))?;
-
-    let project_file_contents = fs::read_to_string(&path).context(format!(
-        indoc! {r#"
-            didn't find the project config file at {}
-
-            1. Create a file at this path with the following contents:
-               {{"project_id": 12345}}
-        "#},
-        path_string
-    ))?;
-
-    let project_data = serde_json::from_str::<ProjectConfig>(&project_file_contents)
-        .with_context(|| format!("bad format for {}", path_string))?;
-
-    // parent directory of path
-    let config_dir = path
-        .parent()
-        .ok_or_else(|| anyhow!("failed to get parent directory of path"))?
-        .to_path_buf();
-
-    // if the config_dir is a directory named ".config" then config_dir's parent, otherwise config_dig
-    let project_dir = if config_dir
-        .file_name()
-        .ok_or_else(|| anyhow!("failed to get file name of config_dir"))?
-        .to_str()
-        .ok_or_else(|| anyhow!("failed to convert file name to string"))?
-        == ".config"
-    {
-        config_dir
-            .parent()
-            .ok_or_else(|| anyhow!("failed to get parent directory of config_dir"))?
-            .to_path_buf()
-    } else {
-        config_dir
-    };
-
-    Ok(Project {
-        harvest_project_id: project_data.harvest_project_id,
-        root: project_dir,
-        name: project_data.name,
-    })
-}
-
-// an enum with three possible states: true, timer not running, timer for different project
-enum TimerStatus {
-    TimerRunning,
-    TimerNotRunning,
-    TimerForDifferentProject,
-}
-
-async fn is_active_timer_for_project(
-    config: &Configuration,
-    project: &Project,
-) -> anyhow::Result<TimerStatus> {
-    // let active_timer = harvest_client.get_active_timer().await?;
-    let me = harvest::retrieve_the_currently_authenticated_user(config)
-        .await
-        .unwrap();
-
-    // let active_timers = harvest::list_time_entries(config).
-
-    let running_timers = harvest::list_time_entries(
-        &config,
-        ListTimeEntriesParams {
-            user_id: me.id,
-            per_page: Some(1),
-            is_running: Some(true),
-            ..Default::default()
-        },
-    )
-    .await?;
-
-    let running_timer = running_timers.time_entries.first();
-
-    match running_timer {
-        Some(timer) => {
-            let timer_project_id = timer.project.as_ref().unwrap().id.unwrap();
-            if timer_project_id == project.harvest_project_id {
-                return Ok(TimerStatus::TimerRunning);
-            } else {
-                return Ok(TimerStatus::TimerForDifferentProject);
-            }
-        }
-        None => {
-            return Ok(TimerStatus::TimerNotRunning);
-        }
-    }
-}
-
-async fn notify_project_timer_status(
-    path: &PathBuf,
-    projects: &Vec<Project>,
-    config: &Configuration,
-) -> anyhow::Result<()> {
-    // what project was this file in?
-    let project = projects
-        .iter()
-        .find(|p| p.contains_file(path.clone()))
-        .ok_or_else(|| anyhow!("path isn't in any project"))
-        .unwrap();
-
-    // use the harvest api to determine if there's an active timer running
-    let active_timer = is_active_timer_for_project(config, project).await?;
-
-    match active_timer {
-        TimerStatus::TimerRunning => {
-            // noop
-        }
-
-        TimerStatus::TimerNotRunning => {
-            Notification::new()
-                .summary("Timer not running")
-                .body(&format!("Start a timer for {}", project.name))
-                .sound_name("Sosumi")
-                .show()
-                .unwrap();
-        }
-        TimerStatus::TimerForDifferentProject => {
-            Notification::new()
-                .summary("Timer running for other project")
-                .body(&format!("Start a timer for {}", project.name))
-                .sound_name("Sosumi")
-                .show()
-                .unwrap();
-        }
-    };
-
-    Ok(())
-}
@@ -0,0 +1,161 @@
+use std::{collections::HashSet, path::Path};
+
+use crate::{auth, config::Settings, control, decimal_hours_to_string, project, state::State};
+
+/// prints the running timer for the macOS menu bar in the format of xbar and
+/// SwiftBar plugins: the first line is the title and the lines after `---` its
+/// menu, whose items run clockhand to stop, start or switch timers, pause
+/// watch and open the report. what watch has seen comes from its control
+/// socket, as for `watch status`
+pub async fn menubar(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    profile: Option<&str>,
+) -> anyhow::Result<()> {
+    let exe = std::env::current_exe()?;
+    // items run clockhand with the same profile, in a terminal when they may
+    // ask something
+    let run = |args: &[&str], terminal: bool| {
+        let args: Vec<&str> = profile
+            .map(|profile| vec!["--profile", profile])
+            .unwrap_or_default()
+            .into_iter()
+            .chain(args.iter().copied())
+            .collect();
+        let params: Vec<String> = args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| format!("param{}={:?}", i + 1, arg))
+            .collect();
+        format!(
+            "bash={:?} {} terminal={} refresh=true",
+            exe.display().to_string(),
+            params.join(" "),
+            terminal
+        )
+    };
+
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+    let today = settings.today();
+    let entries = tracker.time_entries(today, today).await?;
+    let running = entries.iter().find(|e| e.is_running);
+    let running_project = running.map(|e| e.project.id);
+    let total: f32 = entries.iter().map(|e| e.current_hours()).sum();
+
+    let watch = control::send(config_dir, control::Request::Status).ok();
+    let untracked: i64 = watch
+        .iter()
+        .flat_map(|watch| watch.projects.iter())
+        .map(|p| p.untracked_minutes())
+        .sum();
+
+    match running {
+        Some(running) => println!(
+            "{} {}",
+            text(&running.project.name),
+            decimal_hours_to_string(running.current_hours()).trim()
+        ),
+        None if untracked > 0 => println!("No timer, {} min untracked | color=red", untracked),
+        None => println!("No timer"),
+    }
+    println!("---");
+
+    if let Some(running) = running {
+        println!(
+            "{} ({}) | disabled=true",
+            text(&running.project.name),
+            text(&running.task.name)
+        );
+        println!("Stop | {}", run(&["stop"], false));
+    }
+    println!(
+        "Today: {} | disabled=true",
+        decimal_hours_to_string(total).trim()
+    );
+
+    // with the project config's task or the one last used, so starting one
+    // needn't ask
+    let last_tasks = State::load(config_dir)?.last_tasks;
+    let mut seen = HashSet::new();
+    let projects: Vec<_> = project::known_projects(settings)
+        .into_iter()
+        .filter(|p| seen.insert(p.harvest_project_id))
+        .filter(|p| Some(p.harvest_project_id) != running_project)
+        .collect();
+    if !projects.is_empty() {
+        println!(
+            "{}",
+            if running.is_some() {
+                "Switch to"
+            } else {
+                "Start"
+            }
+        );
+        for project in projects {
+            let id = project.harvest_project_id.to_string();
+            let task = project.task.clone().or_else(|| {
+                last_tasks
+                    .get(&project.harvest_project_id)
+                    .map(i32::to_string)
+            });
+            let mut args = Vec::new();
+            if let Some(account) = project.account.as_deref() {
+                args.extend(["--account", account]);
+            }
+            args.extend(["switch", id.as_str()]);
+            if let Some(task) = task.as_deref() {
+                args.extend(["--task", task]);
+            }
+            println!("--{} | {}", text(&project.name), run(&args, task.is_none()));
+        }
+    }
+    println!("---");
+
+    match watch {
+        Some(watch) => {
+            for project in watch.projects.iter() {
+                if project.untracked_minutes() > 0 {
+                    println!(
+                        "Active {} min on {} without a timer | disabled=true",
+                        project.untracked_minutes(),
+                        text(&project.name)
+                    );
+                }
+                if let Some(until) = project.snoozed_until {
+                    println!(
+                        "{} snoozed until {} | disabled=true",
+                        text(&project.name),
+                        until.with_timezone(&chrono::Local).format("%H:%M")
+                    );
+                }
+            }
+            if let Some(last) = &watch.last_notification {
+                println!(
+                    "Last notified {}: {} | disabled=true",
+                    last.at.format("%H:%M"),
+                    text(&last.summary)
+                );
+            }
+            if watch.paused {
+                println!("Resume watch | {}", run(&["watch", "resume"], false));
+            } else {
+                println!("Pause watch | {}", run(&["watch", "pause"], false));
+            }
+        }
+        None => println!("watch isn't running | disabled=true"),
+    }
+    println!("---");
+
+    println!("Report | {}", run(&["report"], true));
+    println!("Refresh | refresh=true");
+
+    Ok(())
+}
+
+/// menu text without the `|` that starts an item's parameters
+fn text(s: &str) -> String {
+    s.replace('|', "¦")
+}
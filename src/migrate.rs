@@ -0,0 +1,212 @@
+use std::{fs, io, path::Path};
+
+use anyhow::Context;
+
+use crate::project::is_toml;
+
+/// the version written to clockhand.toml by this build
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// the version written to project configs by this build
+pub const PROJECT_CONFIG_VERSION: u32 = 1;
+
+const SETTINGS_MIGRATIONS: &[fn(&mut Document)] = &[introduce_version];
+const PROJECT_CONFIG_MIGRATIONS: &[fn(&mut Document)] = &[introduce_version];
+
+/// version 1 only introduced the version field, which is set after migrating
+fn introduce_version(_: &mut Document) {}
+
+#[derive(Clone, Copy)]
+pub enum ConfigKind {
+    Settings,
+    Project,
+}
+
+impl ConfigKind {
+    fn current_version(&self) -> u32 {
+        match self {
+            ConfigKind::Settings => SETTINGS_VERSION,
+            ConfigKind::Project => PROJECT_CONFIG_VERSION,
+        }
+    }
+
+    /// migrations\[n\] upgrades a file from version n to n + 1, files written
+    /// before versioning existed are version 0
+    fn migrations(&self) -> &'static [fn(&mut Document)] {
+        match self {
+            ConfigKind::Settings => SETTINGS_MIGRATIONS,
+            ConfigKind::Project => PROJECT_CONFIG_MIGRATIONS,
+        }
+    }
+}
+
+/// a parsed config file that keeps its formatting, so comments in toml files
+/// survive an upgrade
+enum Document {
+    Json(serde_json::Value),
+    Toml(toml_edit::Document),
+}
+
+impl Document {
+    fn parse(path: &Path, contents: &str) -> anyhow::Result<Document> {
+        if is_toml(path) {
+            Ok(Document::Toml(contents.parse()?))
+        } else {
+            Ok(Document::Json(serde_json::from_str(contents)?))
+        }
+    }
+
+    fn version(&self) -> u32 {
+        let version = match self {
+            Document::Json(value) => value.get("version").and_then(|v| v.as_u64()),
+            Document::Toml(document) => document
+                .get("version")
+                .and_then(|v| v.as_integer())
+                .map(|v| v as u64),
+        };
+        version.unwrap_or(0) as u32
+    }
+
+    fn set_version(&mut self, version: u32) {
+        match self {
+            // first, as it says how to read the rest
+            Document::Json(value) => {
+                if let Some(object) = value.as_object_mut() {
+                    object.shift_insert(0, "version".to_string(), version.into());
+                }
+            }
+            Document::Toml(document) => {
+                document["version"] = toml_edit::value(version as i64);
+            }
+        }
+    }
+
+    fn render(&self) -> anyhow::Result<String> {
+        match self {
+            Document::Json(value) => Ok(serde_json::to_string_pretty(value)? + "\n"),
+            Document::Toml(document) => Ok(document.to_string()),
+        }
+    }
+}
+
+/// upgrades a config file written by an older clockhand in memory and returns
+/// the contents to parse, leaving the file itself to `migrate_file`
+pub fn migrate(path: &Path, contents: String, kind: ConfigKind) -> anyhow::Result<String> {
+    if let Ok(document) = Document::parse(path, &contents) {
+        let current_version = kind.current_version();
+        if document.version() > current_version {
+            eprintln!(
+                "warning: {} is version {} but this {} only understands up to version {}, \
+                 some settings may be ignored; upgrade {} to use them",
+                path.display(),
+                document.version(),
+                env!("CARGO_PKG_NAME"),
+                current_version,
+                env!("CARGO_PKG_NAME")
+            );
+        }
+    }
+
+    Ok(upgrade(path, &contents, kind)?
+        .map(|(_, migrated)| migrated)
+        .unwrap_or(contents))
+}
+
+/// rewrites a config file written by an older clockhand in place, keeping the
+/// original next to it, for `doctor` and `config validate`
+pub fn migrate_file(path: &Path, kind: ConfigKind) -> anyhow::Result<()> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let Some((version, migrated)) = upgrade(path, &contents, kind)? else {
+        return Ok(());
+    };
+
+    let backup = path.with_file_name(format!(
+        "{}.v{}.bak",
+        path.file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        version
+    ));
+    fs::write(&backup, &contents)
+        .and_then(|_| fs::write(path, &migrated))
+        .with_context(|| format!("couldn't upgrade {}", path.display()))?;
+
+    println!(
+        "upgraded {} from version {} to {}, the old file is at {}",
+        path.display(),
+        version,
+        kind.current_version(),
+        backup.display()
+    );
+    Ok(())
+}
+
+/// the version a config file is at and its contents in the current format,
+/// when it's older than this build understands
+fn upgrade(path: &Path, contents: &str, kind: ConfigKind) -> anyhow::Result<Option<(u32, String)>> {
+    let Ok(mut document) = Document::parse(path, contents) else {
+        // leave syntax errors to be reported by the real parser
+        return Ok(None);
+    };
+
+    let version = document.version();
+    if version >= kind.current_version() {
+        return Ok(None);
+    }
+
+    for migration in &kind.migrations()[version as usize..kind.current_version() as usize] {
+        migration(&mut document);
+    }
+    document.set_version(kind.current_version());
+    Ok(Some((version, document.render()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_order_of_json_keys() {
+        let contents = r#"{"name": "Acme", "harvest_project_id": 12345, "auto_start": true}"#;
+        let (version, migrated) =
+            upgrade(Path::new("clockhand.json"), contents, ConfigKind::Project)
+                .unwrap()
+                .unwrap();
+        assert_eq!(version, 0);
+        let keys: Vec<&str> = migrated
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('"')?.split('"').next())
+            .collect();
+        assert_eq!(
+            keys,
+            ["version", "name", "harvest_project_id", "auto_start"]
+        );
+    }
+
+    #[test]
+    fn keeps_comments_and_order_in_toml() {
+        let contents = "# acme's site\nname = \"Acme\"\nharvest_project_id = 12345\n";
+        let (_, migrated) = upgrade(Path::new("clockhand.toml"), contents, ConfigKind::Project)
+            .unwrap()
+            .unwrap();
+        assert!(
+            migrated.starts_with("# acme's site\nname = \"Acme\"\nharvest_project_id = 12345\n")
+        );
+        assert!(migrated.contains("version = 1"));
+    }
+
+    #[test]
+    fn leaves_current_and_newer_files_alone() {
+        for contents in [r#"{"version": 1}"#, r#"{"version": 2}"#] {
+            assert!(
+                upgrade(Path::new("clockhand.json"), contents, ConfigKind::Project)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+}
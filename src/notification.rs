@@ -0,0 +1,208 @@
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+/// the sound clockhand.toml defaults to, which only macOS has
+const MAC_DEFAULT_SOUND: &str = "Sosumi";
+
+/// how a notification with a button was answered. windows toasts have no
+/// buttons, so there they're always dismissed
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(windows, allow(dead_code))]
+pub enum Answer {
+    /// the main button, e.g. Start
+    Action,
+
+    /// the close button, e.g. Snooze
+    Close,
+
+    /// dismissed or timed out without choosing
+    Dismissed,
+}
+
+/// how insistent a notification is, which linux desktops show differently,
+/// e.g. critical ones stay until they're answered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// sends notifications as the terminal on macOS, where they'd otherwise need
+/// an app bundle. other platforms need no setup
+pub fn setup() -> anyhow::Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let bundle_id = mac_notification_sys::get_bundle_identifier_or_default("Terminal");
+        mac_notification_sys::set_application(&bundle_id)?;
+    }
+    Ok(())
+}
+
+/// shows a notification with a sound, a name from /System/Library/Sounds on
+/// macOS, from the freedesktop sound theme on linux or a toast sound like
+/// "Reminder" on windows
+pub fn show(summary: &str, body: &str, sound: &str) -> anyhow::Result<()> {
+    Notification::new()
+        .summary(summary)
+        .body(body)
+        .sound_name(&platform_sound(sound))
+        .show()?;
+    Ok(())
+}
+
+/// shows a notification with two buttons and waits for one to be pressed,
+/// blocking, so async code should call it with `spawn_blocking`
+pub fn ask(
+    summary: &str,
+    body: &str,
+    action: &str,
+    close: &str,
+    sound: &str,
+    urgency: Urgency,
+) -> anyhow::Result<Answer> {
+    #[cfg(target_os = "macos")]
+    {
+        use mac_notification_sys::{send_notification, MainButton, NotificationResponse};
+
+        // notification center has no notion of urgency
+        let _ = urgency;
+
+        let response = send_notification(
+            summary,
+            None,
+            body,
+            Some(
+                &mac_notification_sys::Notification::new()
+                    .main_button(MainButton::SingleAction(action))
+                    .close_button(close)
+                    .sound(sound),
+            ),
+        )?;
+        Ok(match response {
+            NotificationResponse::ActionButton(_) => Answer::Action,
+            NotificationResponse::CloseButton(_) => Answer::Close,
+            _ => Answer::Dismissed,
+        })
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let handle = Notification::new()
+            .summary(summary)
+            .body(body)
+            .action("action", action)
+            .action("close", close)
+            .sound_name(&platform_sound(sound))
+            .urgency(match urgency {
+                Urgency::Low => notify_rust::Urgency::Low,
+                Urgency::Normal => notify_rust::Urgency::Normal,
+                Urgency::Critical => notify_rust::Urgency::Critical,
+            })
+            .show()?;
+
+        let mut answer = Answer::Dismissed;
+        handle.wait_for_action(|key| {
+            answer = match key {
+                "action" => Answer::Action,
+                "close" => Answer::Close,
+                _ => Answer::Dismissed,
+            }
+        });
+        Ok(answer)
+    }
+
+    #[cfg(not(unix))]
+    {
+        // toasts can only report button presses to a registered app, so the
+        // question goes unanswered and reminders stay as they are
+        let _ = (action, close, urgency);
+        show(summary, body, sound)?;
+        Ok(Answer::Dismissed)
+    }
+}
+
+/// asks with a dialog on screen, which is harder to miss than a notification,
+/// blocking until it's answered or given up on after ten minutes. falls back
+/// to a notification where there's no way to show one
+pub fn dialog(
+    summary: &str,
+    body: &str,
+    action: &str,
+    close: &str,
+    sound: &str,
+) -> anyhow::Result<Answer> {
+    #[cfg(target_os = "macos")]
+    {
+        // dialogs are silent
+        let _ = sound;
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "display dialog {} with title {} buttons {{{}, {}}} default button {} giving up after 600",
+            quote(body),
+            quote(summary),
+            quote(close),
+            quote(action),
+            quote(action)
+        );
+        let output = std::process::Command::new("osascript")
+            .args(["-e", &script])
+            .output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // "button returned:Start, gave up:false"
+        Ok(if stdout.contains("gave up:true") {
+            Answer::Dismissed
+        } else if stdout.contains(&format!("button returned:{}", action)) {
+            Answer::Action
+        } else if stdout.contains(&format!("button returned:{}", close)) {
+            Answer::Close
+        } else {
+            Answer::Dismissed
+        })
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let status = std::process::Command::new("zenity")
+            .args([
+                "--question",
+                "--title",
+                summary,
+                "--text",
+                body,
+                "--ok-label",
+                action,
+                "--cancel-label",
+                close,
+                "--timeout",
+                "600",
+            ])
+            .status();
+        // zenity exits 0 for ok, 1 for cancel and 5 on timing out
+        match status.ok().and_then(|s| s.code()) {
+            Some(0) => Ok(Answer::Action),
+            Some(1) => Ok(Answer::Close),
+            Some(5) => Ok(Answer::Dismissed),
+            _ => ask(summary, body, action, close, sound, Urgency::Critical),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        ask(summary, body, action, close, sound, Urgency::Critical)
+    }
+}
+
+/// the configured sound, swapping macOS's default for the freedesktop theme's
+/// equivalent on linux, or the default toast sound on windows
+fn platform_sound(sound: &str) -> String {
+    if cfg!(target_os = "macos") || sound != MAC_DEFAULT_SOUND {
+        sound.to_string()
+    } else if cfg!(windows) {
+        "Default".to_string()
+    } else {
+        "message-new-instant".to_string()
+    }
+}
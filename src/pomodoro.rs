@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use tokio::time::{sleep, Duration};
+
+use crate::{
+    config::Settings,
+    notification,
+    state::State,
+    timer::{self, EntryTarget},
+};
+
+/// starts a timer and alternates work and breaks, stopping the harvest entry
+/// for each break and restarting it after, until interrupted with ctrl-c
+pub async fn pomodoro(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    target: EntryTarget,
+    work: u64,
+    break_minutes: u64,
+) -> anyhow::Result<()> {
+    let (entry, tracker) = timer::start(config_dir, settings, account, target, None).await?;
+    let project = entry.project.name.clone();
+
+    loop {
+        println!("Working for {} minutes", work);
+        if !wait(work).await {
+            let running = tracker.running_time_entry().await?;
+            if let Some(running) = running.filter(|r| r.id == entry.id) {
+                println!(
+                    "{}",
+                    timer::stop_entry(tracker.as_ref(), settings, &running, None).await?
+                );
+            }
+            return Ok(());
+        }
+
+        // paused without rounding, that's left for when the entry is done
+        tracker.stop_time_entry(&entry).await?;
+        let completed = record_pomodoro(config_dir, settings)?;
+        notify(
+            settings,
+            "Time for a break",
+            &format!(
+                "{} pomodoro(s) today, {} is paused for {} minutes",
+                completed, project, break_minutes
+            ),
+        )?;
+
+        println!("Break for {} minutes", break_minutes);
+        if !wait(break_minutes).await {
+            return Ok(());
+        }
+
+        tracker.restart_time_entry(&entry).await?;
+        notify(
+            settings,
+            "Back to work",
+            &format!("Restarted the timer for {}", project),
+        )?;
+    }
+}
+
+/// sleeps for some minutes, returning false when interrupted with ctrl-c
+async fn wait(minutes: u64) -> bool {
+    tokio::select! {
+        _ = sleep(Duration::from_secs(minutes * 60)) => true,
+        _ = tokio::signal::ctrl_c() => false,
+    }
+}
+
+/// counts a completed pomodoro for today, returning today's count
+fn record_pomodoro(config_dir: &Path, settings: &Settings) -> anyhow::Result<u32> {
+    let mut state = State::load(config_dir)?;
+    let count = state.pomodoros.entry(settings.today()).or_default();
+    *count += 1;
+    let count = *count;
+    state.save(config_dir)?;
+    Ok(count)
+}
+
+fn notify(settings: &Settings, summary: &str, body: &str) -> anyhow::Result<()> {
+    println!("{}: {}", summary, body);
+    notification::show(summary, body, &settings.notifications.sound)
+}
@@ -0,0 +1,550 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, EscalationStep, Settings, Templates},
+    logging,
+    migrate::{self, ConfigKind},
+    notification::Urgency,
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProjectConfig {
+    /// format version, see migrate.rs
+    #[serde(default)]
+    pub version: u32,
+
+    pub harvest_project_id: i32,
+    pub name: String,
+
+    /// named account (as in `--account`) the project is billed to, when it
+    /// isn't the default one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+
+    /// how `stop` rounds the hours of the project's time entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<Rounding>,
+
+    /// task name or id timers are started with when `--task` isn't passed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+
+    /// whether timers default to a billable or non-billable task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billable: Option<bool>,
+
+    /// lets `watch` start a timer when work on the project starts, instead of
+    /// reminding
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_start: bool,
+
+    /// hours a day to aim for on the project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_target: Option<f32>,
+
+    /// globs relative to the project root, e.g. "*.log", whose changes don't
+    /// count as activity
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// globs relative to the project root, e.g. "src/**", that are the only
+    /// files whose changes count as activity, when any are given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+
+    /// seconds between `watch` reminders for the project, instead of the
+    /// global interval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+
+    /// sound for the project's reminders, instead of the one in
+    /// [notifications]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+
+    /// how insistent the project's reminders are: low, normal or critical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<Urgency>,
+
+    /// how ignored reminders escalate, instead of `escalation` in [watch]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escalation: Vec<EscalationStep>,
+
+    /// applications, e.g. "Figma", whose use counts as work on the project
+    /// when `watch --track-apps` is on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
+
+    /// globs matched against the front window's title, ignoring case, e.g.
+    /// "*acme*", for `watch --track-apps`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub window_titles: Vec<String>,
+
+    /// wording of the project's reminders, instead of the templates in
+    /// [notifications]
+    #[serde(default, skip_serializing_if = "Templates::is_empty")]
+    pub templates: Templates,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoundingMode {
+    #[default]
+    Up,
+    Nearest,
+    Down,
+}
+
+/// rounds time entries to a multiple of some minutes, as agencies often bill
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Rounding {
+    /// minutes entries are rounded to a multiple of, e.g. 6 or 15
+    pub increment: u32,
+
+    #[serde(default)]
+    pub mode: RoundingMode,
+
+    /// minutes an entry is at least, after rounding
+    #[serde(default)]
+    pub minimum: u32,
+}
+
+impl Rounding {
+    /// rounds decimal hours
+    pub fn apply(&self, hours: f32) -> f32 {
+        // whole minutes first, so 0.1h isn't rounded up for float noise
+        let minutes = (hours * 60.0).round();
+        let increment = self.increment.max(1) as f32;
+        let rounded = match self.mode {
+            RoundingMode::Up => (minutes / increment).ceil(),
+            RoundingMode::Nearest => (minutes / increment).round(),
+            RoundingMode::Down => (minutes / increment).floor(),
+        } * increment;
+
+        rounded.max(self.minimum as f32) / 60.0
+    }
+}
+
+#[derive(Clone)]
+pub struct Project {
+    pub harvest_project_id: i32,
+    pub root: PathBuf,
+    pub name: String,
+    pub account: Option<String>,
+    pub rounding: Option<Rounding>,
+    pub task: Option<String>,
+    pub billable: Option<bool>,
+    pub auto_start: bool,
+    pub daily_target: Option<f32>,
+    pub config_path: PathBuf,
+
+    /// rules from the root's .gitignore and .ignore files
+    pub ignore_rules: Gitignore,
+
+    /// `ignore` and `only` from the config
+    pub ignore: Vec<glob::Pattern>,
+    pub only: Vec<glob::Pattern>,
+
+    /// reminder overrides from the config
+    pub interval: Option<u64>,
+    pub sound: Option<String>,
+    pub urgency: Option<Urgency>,
+    pub escalation: Vec<EscalationStep>,
+
+    /// `apps` and `window_titles` from the config
+    pub apps: Vec<String>,
+    pub window_titles: Vec<glob::Pattern>,
+
+    pub templates: Templates,
+}
+
+impl Project {
+    pub fn contains_file(&self, path: PathBuf) -> bool {
+        path.starts_with(&self.root)
+    }
+
+    /// the branch checked out in the project's git repository, if it's one
+    /// and HEAD isn't detached
+    pub fn git_branch(&self) -> Option<String> {
+        let dot_git = self.root.join(".git");
+        // worktrees and submodules have a .git file pointing elsewhere
+        let git_dir = if dot_git.is_file() {
+            let contents = fs::read_to_string(&dot_git).ok()?;
+            self.root
+                .join(contents.trim().strip_prefix("gitdir:")?.trim())
+        } else {
+            dot_git
+        };
+        let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        head.trim()
+            .strip_prefix("ref: refs/heads/")
+            .map(str::to_string)
+    }
+
+    /// the project's name, followed by its git branch when there is one, e.g.
+    /// "Acme (feature/sso-login)"
+    pub fn name_with_branch(&self) -> String {
+        match self.git_branch() {
+            Some(branch) => format!("{} ({})", self.name, branch),
+            None => self.name.clone(),
+        }
+    }
+
+    /// whether another project's root is inside this one's, or the same
+    pub fn overlaps(&self, other: &Project) -> bool {
+        other.root.starts_with(&self.root) && other.config_path != self.config_path
+    }
+
+    /// whether changes to a file are noise rather than work, as it's in .git,
+    /// excluded by the config's `ignore` and `only` or by the project's
+    /// .gitignore or .ignore, e.g. build output
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        relative.components().any(|c| c.as_os_str() == ".git")
+            || self.ignore.iter().any(|p| p.matches_path(relative))
+            || (!self.only.is_empty() && !self.only.iter().any(|p| p.matches_path(relative)))
+            || self
+                .ignore_rules
+                .matched_path_or_any_parents(path, path.is_dir())
+                .is_ignore()
+    }
+}
+
+/// names of files with ignore rules in a project root
+pub const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// the rules from a project root's ignore files, with none for files that are
+/// missing or unreadable
+fn ignore_rules(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for name in IGNORE_FILE_NAMES {
+        let path = root.join(name);
+        if path.exists() {
+            if let Some(e) = builder.add(&path) {
+                logging::warn!("couldn't read {}: {}", path.display(), e);
+            }
+        }
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+pub fn read_project_config(path: &PathBuf) -> anyhow::Result<Project> {
+    let path_string = path
+        .as_os_str()
+        .to_str()
+        .ok_or_else(|| anyhow!("failed to generate a path to project config"))?;
+
+    let project_file_contents = fs::read_to_string(path).context(format!(
+        indoc! {r#"
+            didn't find the project config file at {}
+
+            run `{} init` in the project, or create a file at this path with
+            the following contents:
+               {{"harvest_project_id": 12345, "name": "My project"}}
+            or, for a clockhand.toml:
+               harvest_project_id = 12345
+               name = "My project"
+        "#},
+        path_string,
+        env!("CARGO_PKG_NAME")
+    ))?;
+
+    let project_file_contents = migrate::migrate(path, project_file_contents, ConfigKind::Project)?;
+    let project_data = parse_project_config(path, &project_file_contents)
+        .with_context(|| format!("bad format for {}", path_string))?;
+
+    let root = project_root(path)?;
+    Ok(Project {
+        harvest_project_id: project_data.harvest_project_id,
+        ignore_rules: ignore_rules(&root),
+        root,
+        name: project_data.name,
+        account: project_data.account,
+        rounding: project_data.rounding,
+        task: project_data.task,
+        billable: project_data.billable,
+        auto_start: project_data.auto_start,
+        daily_target: project_data.daily_target,
+        config_path: path.clone(),
+        ignore: patterns(&project_data.ignore)
+            .with_context(|| format!("bad ignore pattern in {}", path_string))?,
+        only: patterns(&project_data.only)
+            .with_context(|| format!("bad only pattern in {}", path_string))?,
+        interval: project_data.interval,
+        sound: project_data.sound,
+        urgency: project_data.urgency,
+        escalation: project_data.escalation,
+        apps: project_data.apps,
+        window_titles: patterns(&project_data.window_titles)
+            .with_context(|| format!("bad window_titles pattern in {}", path_string))?,
+        templates: project_data.templates,
+    })
+}
+
+fn patterns(globs: &[String]) -> anyhow::Result<Vec<glob::Pattern>> {
+    globs
+        .iter()
+        .map(|g| glob::Pattern::new(g).with_context(|| format!("{:?}", g)))
+        .collect()
+}
+
+/// file names project configs can have, either in the project root or its
+/// .config directory
+pub const CONFIG_FILE_NAMES: [&str; 2] = [
+    concat!(env!("CARGO_PKG_NAME"), ".json"),
+    concat!(env!("CARGO_PKG_NAME"), ".toml"),
+];
+
+pub fn is_project_config_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .is_some_and(|f| CONFIG_FILE_NAMES.contains(&f))
+}
+
+/// finds the project config for a directory by looking in it and each of its
+/// parents, like git does for .git
+pub fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find_map(|ancestor| {
+        [ancestor.to_path_buf(), ancestor.join(".config")]
+            .into_iter()
+            .flat_map(|d| CONFIG_FILE_NAMES.map(|name| d.join(name)))
+            .find(|path| path.is_file())
+    })
+}
+
+/// the project a file belongs to, the one with the most specific root when
+/// one project is nested in another, e.g. a vendored repository
+pub fn project_for_file<'a>(projects: &'a [Project], path: &Path) -> Option<&'a Project> {
+    projects
+        .iter()
+        .filter(|p| p.contains_file(path.to_path_buf()))
+        .max_by_key(|p| p.root.components().count())
+}
+
+/// reads the project config for the current directory
+pub fn current_project() -> anyhow::Result<Project> {
+    let cwd = env::current_dir()?;
+    let path = find_project_config(&cwd).ok_or_else(|| {
+        anyhow!(
+            "didn't find a {} in {} or its parents, run `{} init` to create one",
+            CONFIG_FILE_NAMES[0],
+            cwd.display(),
+            env!("CARGO_PKG_NAME")
+        )
+    })?;
+    read_project_config(&path)
+}
+
+/// finds the config for a harvest project, looking at the current directory's
+/// project and then the ones `watch` is configured for
+pub fn find_project(settings: &Settings, harvest_project_id: i32) -> Option<Project> {
+    known_projects(settings)
+        .into_iter()
+        .find(|project| project.harvest_project_id == harvest_project_id)
+}
+
+/// the current directory's project followed by the ones `watch` is configured
+/// for, skipping configs that can't be read
+pub fn known_projects(settings: &Settings) -> Vec<Project> {
+    let watched = config::expand_paths(&settings.watch.projects)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|path| {
+            if path.is_dir() {
+                discover_project_configs(&path)
+            } else {
+                vec![path]
+            }
+        })
+        .filter_map(|path| read_project_config(&path).ok());
+
+    current_project().ok().into_iter().chain(watched).collect()
+}
+
+/// finds project configs anywhere under a directory, skipping whatever
+/// .gitignore and .ignore files exclude
+pub fn discover_project_configs(root: &Path) -> Vec<PathBuf> {
+    ignore::WalkBuilder::new(root)
+        // project configs may live in a .config directory
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| is_project_config_name(path))
+        .collect()
+}
+
+/// whether a project config is written in toml rather than json, going by its
+/// extension
+pub fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "toml")
+}
+
+fn parse_project_config(path: &Path, contents: &str) -> anyhow::Result<ProjectConfig> {
+    if is_toml(path) {
+        Ok(toml::from_str(contents)?)
+    } else {
+        Ok(serde_json::from_str(contents)?)
+    }
+}
+
+/// the directory a project config applies to, which only depends on where the
+/// config file is
+pub fn project_root(path: &Path) -> anyhow::Result<PathBuf> {
+    // parent directory of path
+    let config_dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("failed to get parent directory of path"))?
+        .to_path_buf();
+
+    // if the config_dir is a directory named ".config" then config_dir's parent, otherwise config_dig
+    let project_dir = if config_dir
+        .file_name()
+        .ok_or_else(|| anyhow!("failed to get file name of config_dir"))?
+        .to_str()
+        .ok_or_else(|| anyhow!("failed to convert file name to string"))?
+        == ".config"
+    {
+        config_dir
+            .parent()
+            .ok_or_else(|| anyhow!("failed to get parent directory of config_dir"))?
+            .to_path_buf()
+    } else {
+        config_dir
+    };
+
+    Ok(project_dir)
+}
+
+/// makes a config path absolute and resolves symlinks in its directory, so it
+/// can be compared with paths in watcher events even if the file is missing
+pub fn canonical_config_path(path: &Path) -> PathBuf {
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return path.to_path_buf();
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+
+    // dunce avoids windows' \\?\ paths, which never match the ones in events
+    dunce::canonicalize(parent)
+        .map(|dir| dir.join(file_name))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounding(increment: u32, mode: RoundingMode, minimum: u32) -> Rounding {
+        Rounding {
+            increment,
+            mode,
+            minimum,
+        }
+    }
+
+    #[test]
+    fn rounds_to_the_increment_in_each_mode() {
+        let hours = 20.0 / 60.0;
+        assert_eq!(rounding(15, RoundingMode::Up, 0).apply(hours), 0.5);
+        assert_eq!(rounding(15, RoundingMode::Nearest, 0).apply(hours), 0.25);
+        assert_eq!(rounding(15, RoundingMode::Down, 0).apply(hours), 0.25);
+        assert_eq!(rounding(6, RoundingMode::Up, 0).apply(hours), 0.4);
+    }
+
+    #[test]
+    fn leaves_whole_increments_alone_despite_float_noise() {
+        assert_eq!(rounding(6, RoundingMode::Up, 0).apply(0.1), 0.1);
+        assert_eq!(rounding(15, RoundingMode::Up, 0).apply(0.75), 0.75);
+    }
+
+    #[test]
+    fn rounds_up_to_the_minimum() {
+        assert_eq!(rounding(15, RoundingMode::Up, 30).apply(0.1), 0.5);
+        assert_eq!(rounding(15, RoundingMode::Down, 15).apply(0.0), 0.25);
+        assert_eq!(rounding(15, RoundingMode::Up, 30).apply(1.0), 1.0);
+    }
+
+    #[test]
+    fn treats_a_zero_increment_as_a_minute() {
+        assert_eq!(rounding(0, RoundingMode::Up, 0).apply(0.5), 0.5);
+    }
+
+    fn project(root: &str) -> Project {
+        Project {
+            harvest_project_id: 1,
+            root: PathBuf::from(root),
+            name: root.to_string(),
+            account: None,
+            rounding: None,
+            task: None,
+            billable: None,
+            auto_start: false,
+            daily_target: None,
+            config_path: Path::new(root).join("clockhand.json"),
+            ignore_rules: Gitignore::empty(),
+            ignore: Vec::new(),
+            only: Vec::new(),
+            interval: None,
+            sound: None,
+            urgency: None,
+            escalation: Vec::new(),
+            apps: Vec::new(),
+            window_titles: Vec::new(),
+            templates: Templates::default(),
+        }
+    }
+
+    fn name_for<'a>(projects: &'a [Project], path: &str) -> Option<&'a str> {
+        project_for_file(projects, Path::new(path)).map(|p| p.name.as_str())
+    }
+
+    #[test]
+    fn picks_the_most_specific_of_nested_roots() {
+        // in either order, the vendored repository wins inside itself
+        for projects in [
+            [project("/a"), project("/a/vendor/b")],
+            [project("/a/vendor/b"), project("/a")],
+        ] {
+            assert_eq!(
+                name_for(&projects, "/a/vendor/b/src/lib.rs"),
+                Some("/a/vendor/b")
+            );
+            assert_eq!(name_for(&projects, "/a/vendor/c.rs"), Some("/a"));
+            assert_eq!(name_for(&projects, "/a/src/main.rs"), Some("/a"));
+        }
+    }
+
+    #[test]
+    fn doesnt_match_a_sibling_sharing_a_prefix() {
+        let projects = [project("/a/foo"), project("/a/foobar")];
+        assert_eq!(name_for(&projects, "/a/foobar/x.rs"), Some("/a/foobar"));
+        assert_eq!(name_for(&projects, "/a/foo/x.rs"), Some("/a/foo"));
+
+        let projects = [project("/a/foo")];
+        assert_eq!(name_for(&projects, "/a/foobar/x.rs"), None);
+    }
+
+    #[test]
+    fn matches_nothing_outside_every_root() {
+        let projects = [project("/a/foo"), project("/a/bar")];
+        assert_eq!(name_for(&projects, "/b/foo/x.rs"), None);
+        assert_eq!(name_for(&projects, "/a/x.rs"), None);
+    }
+}
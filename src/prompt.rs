@@ -0,0 +1,34 @@
+use std::io::{self, IsTerminal, Write};
+
+use dialoguer::{theme::ColorfulTheme, FuzzySelect};
+
+/// reads a trimmed line from stdin after printing a label
+pub fn prompt(label: &str) -> anyhow::Result<String> {
+    print!("{}", label);
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// asks a yes/no question, defaulting to no
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+    let answer = prompt(&format!("{} [y/N] ", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// whether there's someone at the terminal to answer prompts
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// lets the user type to filter `items` and pick one, returning its index
+pub fn fuzzy_select(label: &str, items: &[String], default: usize) -> anyhow::Result<usize> {
+    FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(label)
+        .items(items)
+        .default(default)
+        .interact()
+        .map_err(Into::into)
+}
@@ -0,0 +1,56 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Mutex;
+
+use crate::logging;
+
+/// requests harvest allows in each `WINDOW`
+const BUDGET: f64 = 100.0;
+
+const WINDOW: Duration = Duration::from_secs(15);
+
+/// a token bucket shared by every call to the harvest api, refilled at the
+/// rate harvest allows and holding at most a window's worth
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+fn bucket() -> &'static Mutex<Bucket> {
+    static BUCKET: OnceLock<Mutex<Bucket>> = OnceLock::new();
+    BUCKET.get_or_init(|| {
+        Mutex::new(Bucket {
+            tokens: BUDGET,
+            updated: Instant::now(),
+        })
+    })
+}
+
+/// waits until another harvest request fits in the budget. callers queue in
+/// the order they asked, as the lock is fair, and waiting is logged
+pub async fn acquire() {
+    let mut bucket = bucket().lock().await;
+
+    let now = Instant::now();
+    let per_second = BUDGET / WINDOW.as_secs_f64();
+    bucket.tokens = (bucket.tokens + (now - bucket.updated).as_secs_f64() * per_second).min(BUDGET);
+    bucket.updated = now;
+
+    if bucket.tokens < 1.0 {
+        let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / per_second);
+        logging::warn!(
+            "close to harvest's limit of {} requests every {} seconds, waiting {:.1}s",
+            BUDGET,
+            WINDOW.as_secs(),
+            wait.as_secs_f64()
+        );
+        tokio::time::sleep(wait).await;
+        bucket.tokens = 1.0;
+        bucket.updated = Instant::now();
+    }
+
+    bucket.tokens -= 1.0;
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    io::{self, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::{
+    activity, auth,
+    config::Settings,
+    decimal_hours_to_string, project, prompt, report, theme, timer,
+    tracker::{find_project_assignment, ProjectAssignment, TimeTracker},
+};
+
+/// minutes a bit of activity counts as work for after it's seen
+const TAIL_MINUTES: i64 = 5;
+
+/// untracked time shorter than this isn't offered
+const MIN_MINUTES: i64 = 10;
+
+/// a stretch of activity in one project
+struct Block {
+    harvest_project_id: i32,
+    project: String,
+    start: NaiveTime,
+    end: NaiveTime,
+
+    /// the parts of it without a time entry
+    untracked: Vec<(NaiveTime, NaiveTime)>,
+}
+
+/// clusters a day's activity log into blocks per project, compares them with
+/// the day's time entries and offers to log the blocks that weren't tracked
+pub async fn reconcile(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    day: NaiveDate,
+    gap: u64,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let activity = activity::read(config_dir, settings, day)?;
+    if activity.is_empty() {
+        println!(
+            "there's no activity logged for {}, `{} watch` records it",
+            day,
+            env!("CARGO_PKG_NAME")
+        );
+        return Ok(());
+    }
+
+    // the account each project's billed to, from its config
+    let projects = project::known_projects(settings);
+    let account_for = |project_id: i32| -> Option<String> {
+        projects
+            .iter()
+            .find(|p| p.harvest_project_id == project_id)
+            .and_then(|p| p.account.clone())
+            .or_else(|| account.map(str::to_string))
+    };
+
+    // one tracker per account, for looking up entries and logging blocks
+    let mut trackers: HashMap<Option<String>, Arc<dyn TimeTracker>> = HashMap::new();
+    let mut entries = Vec::new();
+    for a in activity.iter() {
+        let project_account = account_for(a.harvest_project_id);
+        if let Entry::Vacant(vacant) = trackers.entry(project_account) {
+            let tracker = auth::credentials(config_dir, settings, vacant.key().as_deref())
+                .await?
+                .tracker();
+            entries.extend(tracker.time_entries(day, day).await?);
+            vacant.insert(tracker);
+        }
+    }
+
+    // activity closer together than `gap` is one block
+    let gap = chrono::Duration::minutes(gap as i64);
+    let mut by_project: BTreeMap<i32, Vec<&activity::Activity>> = BTreeMap::new();
+    for a in activity.iter() {
+        by_project.entry(a.harvest_project_id).or_default().push(a);
+    }
+    let mut blocks: Vec<Block> = Vec::new();
+    for (project_id, activity) in by_project {
+        let mut spans: Vec<(NaiveTime, NaiveTime)> = Vec::new();
+        for a in activity.iter() {
+            let at = settings.time(a.at);
+            match spans.last_mut() {
+                Some((_, end)) if at - *end <= gap => *end = at.max(*end),
+                _ => spans.push((at, at)),
+            }
+        }
+
+        let project_entries: Vec<_> = entries
+            .iter()
+            .filter(|e| e.project.id == project_id)
+            .collect();
+        let timed: Vec<(NaiveTime, NaiveTime)> = project_entries
+            .iter()
+            .filter_map(|e| report::entry_span(e))
+            .collect();
+        // entries without start and end times cover the earliest activity
+        let mut untimed = chrono::Duration::minutes(
+            (project_entries
+                .iter()
+                .filter(|e| report::entry_span(e).is_none())
+                .map(|e| e.current_hours())
+                .sum::<f32>()
+                * 60.0)
+                .round() as i64,
+        );
+
+        for (start, end) in spans {
+            // activity late in the day still ends it
+            let end = end
+                .overflowing_add_signed(chrono::Duration::minutes(TAIL_MINUTES))
+                .0
+                .max(end);
+            let mut untracked = subtract((start, end), &timed);
+            for piece in untracked.iter_mut() {
+                let length = piece.1 - piece.0;
+                let covered = untimed.min(length);
+                piece.0 += covered;
+                untimed -= covered;
+            }
+            untracked.retain(|(start, end)| start < end);
+
+            blocks.push(Block {
+                harvest_project_id: project_id,
+                project: activity[0].project.clone(),
+                start,
+                end,
+                untracked,
+            });
+        }
+    }
+    blocks.sort_by_key(|b| b.start);
+
+    let minutes = |(start, end): (NaiveTime, NaiveTime)| (end - start).num_minutes();
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    writeln!(&mut tw, "Time\tProject\tActive\tUntracked")?;
+    for block in blocks.iter() {
+        let untracked: i64 = block.untracked.iter().copied().map(minutes).sum();
+        writeln!(
+            &mut tw,
+            "{}–{}\t{}\t{}\t{}",
+            block.start.format("%H:%M"),
+            block.end.format("%H:%M"),
+            theme::project(&settings.theme, block.harvest_project_id, &block.project),
+            decimal_hours_to_string(minutes((block.start, block.end)) as f32 / 60.0).trim(),
+            match untracked {
+                0 => String::new(),
+                untracked => decimal_hours_to_string(untracked as f32 / 60.0)
+                    .trim()
+                    .to_string(),
+            }
+        )?;
+    }
+    tw.flush()?;
+
+    let offers: Vec<(&Block, (NaiveTime, NaiveTime))> = blocks
+        .iter()
+        .flat_map(|b| b.untracked.iter().map(move |piece| (b, *piece)))
+        .filter(|(_, piece)| minutes(*piece) >= MIN_MINUTES)
+        .collect();
+    if offers.is_empty() {
+        println!("\nall the activity on {} was tracked", day);
+        return Ok(());
+    }
+    if !yes && !prompt::is_interactive() {
+        println!(
+            "\n{} untracked block(s), run this in a terminal to log them, or pass --yes",
+            offers.len()
+        );
+        return Ok(());
+    }
+
+    // harvest locks entries in approved or closed periods, so a locked entry
+    // means the whole day is
+    if let Some(locked) = entries.iter().find(|e| e.is_locked) {
+        return Err(anyhow!(
+            "{} is locked{}, time can't be logged for it",
+            day,
+            locked
+                .locked_reason
+                .as_deref()
+                .map(|reason| format!(" ({})", reason))
+                .unwrap_or_default()
+        ));
+    }
+
+    // each project's task is settled before any block's logged, so one that
+    // can't be doesn't leave the day half logged
+    let mut assignments: HashMap<Option<String>, Vec<ProjectAssignment>> = HashMap::new();
+    let mut tasks = HashMap::new();
+    for (block, _) in offers.iter() {
+        if tasks.contains_key(&block.harvest_project_id) {
+            continue;
+        }
+        let project_account = account_for(block.harvest_project_id);
+        if !assignments.contains_key(&project_account) {
+            let tracker = &trackers[&project_account];
+            assignments.insert(
+                project_account.clone(),
+                tracker.project_assignments().await?,
+            );
+        }
+        let assignment = find_project_assignment(
+            &assignments[&project_account],
+            &block.harvest_project_id.to_string(),
+        )?
+        .clone();
+        let task = timer::pick_task(config_dir, settings, &assignment, None, None)?;
+        tasks.insert(block.harvest_project_id, (assignment, task));
+    }
+
+    println!();
+    for (block, piece) in offers {
+        let hours = minutes(piece) as f32 / 60.0;
+        let question = format!(
+            "Log {} for {} ({}–{})?",
+            decimal_hours_to_string(hours).trim(),
+            block.project,
+            piece.0.format("%H:%M"),
+            piece.1.format("%H:%M")
+        );
+        if !yes && !prompt::confirm(&question)? {
+            continue;
+        }
+        let (assignment, task) = &tasks[&block.harvest_project_id];
+        timer::log_task(
+            config_dir,
+            trackers[&account_for(block.harvest_project_id)].as_ref(),
+            assignment,
+            task,
+            day,
+            hours,
+            None,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// what's left of a span after taking out others
+fn subtract(
+    span: (NaiveTime, NaiveTime),
+    others: &[(NaiveTime, NaiveTime)],
+) -> Vec<(NaiveTime, NaiveTime)> {
+    let mut pieces = vec![span];
+    for &(start, end) in others {
+        pieces = pieces
+            .into_iter()
+            .flat_map(|(piece_start, piece_end)| {
+                [
+                    (piece_start, piece_end.min(start)),
+                    (piece_start.max(end), piece_end),
+                ]
+            })
+            .filter(|(start, end)| start < end)
+            .collect();
+    }
+    pieces
+}
@@ -0,0 +1,1443 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api, auth, cache::EntryCache, config::Settings, decimal_hours_to_string, project,
+    start_of_week, state::State, strip_newlines_and_tabs, targets, theme, timer,
+    tracker::TimeEntry,
+};
+
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+    /// days to report on, defaults to this week and last week
+    #[arg(value_enum)]
+    pub period: Option<Period>,
+
+    #[arg(long, value_enum, default_value_t)]
+    pub format: ReportFormat,
+
+    /// prints totals per group instead of each entry
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+
+    /// prints a row per ISO week with total and billable hours, and the
+    /// difference from the weekly target
+    #[arg(long, conflicts_with = "group_by")]
+    pub summary: bool,
+
+    /// prints a row per ISO week with total and billable hours as percentages
+    /// of `capacity` in [targets], and the trend in billable utilization
+    #[arg(long, conflicts_with_all = ["group_by", "summary"])]
+    pub utilization: bool,
+
+    /// draws hours per day over the last --weeks as a calendar, shaded
+    /// against the daily target
+    #[arg(long, conflicts_with_all = ["group_by", "summary", "utilization", "period"])]
+    pub heatmap: bool,
+
+    /// lists workdays before today with fewer hours than --below, skipping
+    /// weekends and `holidays` from the [report] settings
+    #[arg(long, conflicts_with_all = ["group_by", "summary", "utilization", "heatmap"])]
+    pub gaps: bool,
+
+    /// hours a workday needs for --gaps, defaults to `daily` in [targets] or 8
+    #[arg(long, value_parser = timer::parse_hours, requires = "gaps")]
+    pub below: Option<f32>,
+
+    /// lists entries reviewers tend to bounce: overlapping ones, ones without
+    /// notes, ones longer than --max-hours and repeats of a project and notes
+    /// on the same day
+    #[arg(long, conflicts_with_all = ["group_by", "summary", "utilization", "heatmap", "gaps"])]
+    pub audit: bool,
+
+    /// longest an entry can be before --audit flags it
+    #[arg(long, value_parser = timer::parse_hours, default_value = "8h")]
+    pub max_hours: f32,
+
+    /// file to write the html report to, instead of stdout
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+
+    /// weeks the heatmap covers
+    #[arg(long, default_value_t = 12, requires = "heatmap")]
+    pub weeks: u32,
+
+    /// only includes a project, by harvest id or a fuzzy match on its name in
+    /// harvest or its clockhand.json
+    #[arg(long)]
+    pub project: Option<String>,
+
+    /// columns of the text report and their order, e.g.
+    /// date,project,task,hours,notes
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub columns: Option<Vec<Column>>,
+
+    /// only includes entries with this #tag in their notes, with or without
+    /// the #
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    /// only includes billable entries
+    #[arg(long, conflicts_with = "non_billable")]
+    pub billable: bool,
+
+    /// only includes non-billable entries, e.g. internal work
+    #[arg(long)]
+    pub non_billable: bool,
+
+    /// reports on entries cached by earlier reports rather than asking
+    /// harvest, which also happens when harvest can't be reached
+    #[arg(long, conflicts_with = "team")]
+    pub offline: bool,
+
+    /// includes everyone's entries rather than only yours, printing totals per
+    /// person and a project by person table, with an administrator's token
+    #[arg(long)]
+    pub team: bool,
+
+    /// adds each billable entry's amount at its harvest billable rate, and
+    /// totals per group
+    #[arg(long)]
+    pub amounts: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Period {
+    /// today's entries, with a running total
+    Today,
+
+    /// the current week, starting on `week_start` from the [report] settings
+    Week,
+
+    /// the current calendar month
+    Month,
+}
+
+impl Period {
+    /// the first and last days of the period containing `today`
+    pub fn range(self, today: NaiveDate, week_start: Weekday) -> (NaiveDate, NaiveDate) {
+        match self {
+            Period::Today => (today, today),
+            Period::Week => (start_of_week(today, week_start), today),
+            Period::Month => (today.with_day(1).unwrap_or(today), today),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ReportFormat {
+    /// aligned columns for reading
+    #[default]
+    Text,
+
+    /// an array of entries, see `ReportEntry`
+    Json,
+
+    /// a row per entry with the same fields as json, for spreadsheets
+    Csv,
+
+    /// a github flavored table, for pasting into pull requests and updates
+    Markdown,
+
+    /// a standalone page with sortable entries and hours per project, for
+    /// sending to clients
+    Html,
+}
+
+/// a column of the text report, as picked with `--columns` or `columns` in the
+/// [report] settings
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Date,
+    ProjectId,
+    Project,
+    Client,
+    Task,
+    User,
+    Hours,
+    Amount,
+    Status,
+    Notes,
+}
+
+impl Column {
+    /// what the text report prints, before --amounts adds its column
+    pub const DEFAULT: [Column; 7] = [
+        Column::Date,
+        Column::ProjectId,
+        Column::Project,
+        Column::Task,
+        Column::Hours,
+        Column::Status,
+        Column::Notes,
+    ];
+
+    /// an entry's value for the column, colored by the theme
+    fn value(self, settings: &Settings, entry: &TimeEntry) -> String {
+        let project_id = entry.project.id;
+        match self {
+            Column::Date => theme::date(&settings.theme, &entry.spent_date.to_string()).to_string(),
+            Column::ProjectId => project_id.to_string(),
+            Column::Project => theme::project(
+                &settings.theme,
+                project_id,
+                &strip_newlines_and_tabs(&entry.project.name),
+            )
+            .to_string(),
+            Column::Client => strip_newlines_and_tabs(
+                &entry
+                    .client
+                    .as_ref()
+                    .map(|c| c.name.clone())
+                    .unwrap_or_default(),
+            ),
+            Column::Task => strip_newlines_and_tabs(&entry.task.name),
+            Column::User => strip_newlines_and_tabs(&entry.user.name),
+            Column::Hours => decimal_hours_to_string(entry.current_hours()),
+            Column::Amount => format_amount(settings, amount(entry)),
+            Column::Status => entry.status().to_string(),
+            Column::Notes => theme::notes(&settings.theme, entry.notes.as_deref(), 60).to_string(),
+        }
+    }
+}
+
+/// the text report's columns from `--columns`, the settings or the defaults,
+/// with an amount column before the notes for `--amounts` if none was picked
+fn columns(settings: &Settings, args: &ReportArgs) -> Vec<Column> {
+    let mut columns = args
+        .columns
+        .clone()
+        .or_else(|| settings.report.columns.clone())
+        .unwrap_or_else(|| Column::DEFAULT.to_vec());
+    if args.amounts && !columns.contains(&Column::Amount) {
+        let at = columns
+            .iter()
+            .position(|c| *c == Column::Notes)
+            .unwrap_or(columns.len());
+        columns.insert(at, Column::Amount);
+    }
+    columns
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GroupBy {
+    /// hours and entry counts per project
+    Project,
+
+    /// each day's entries under a heading, with the day's total
+    Day,
+
+    /// hours and entry counts per client, as invoiced
+    Client,
+
+    /// hours and entry counts per task, e.g. development versus meetings
+    Task,
+
+    /// hours and entry counts per person, with --team
+    User,
+
+    /// hours and entry counts per #tag in the notes
+    Tag,
+}
+
+/// a time entry as `report --format json` and `--format csv` print it
+///
+/// scripts depend on these field names, so they shouldn't change; add new
+/// fields rather than renaming old ones
+#[derive(Debug, Serialize)]
+pub struct ReportEntry {
+    pub id: Option<String>,
+    pub date: String,
+    pub project_id: Option<i32>,
+    pub project_name: String,
+    pub client: Option<String>,
+    pub task: String,
+    pub hours: f32,
+    pub notes: Option<String>,
+    pub billable: bool,
+    pub running: bool,
+    pub billable_rate: Option<f32>,
+    pub amount: f32,
+    pub locked: bool,
+    pub approval_status: Option<String>,
+    pub user: String,
+}
+
+impl From<&TimeEntry> for ReportEntry {
+    fn from(entry: &TimeEntry) -> Self {
+        ReportEntry {
+            id: Some(entry.id.to_string()),
+            date: entry.spent_date.to_string(),
+            project_id: Some(entry.project.id),
+            project_name: entry.project.name.clone(),
+            client: entry.client.as_ref().map(|c| c.name.clone()),
+            task: entry.task.name.clone(),
+            hours: entry.current_hours(),
+            notes: entry.notes.clone(),
+            billable: entry.billable,
+            running: entry.is_running,
+            billable_rate: entry.billable_rate,
+            amount: amount(entry),
+            locked: entry.is_locked,
+            approval_status: entry.approval_status.clone(),
+            user: entry.user.name.clone(),
+        }
+    }
+}
+
+/// prints timers for a period, this week and last week by default
+pub async fn report(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    args: &ReportArgs,
+) -> anyhow::Result<()> {
+    let today = settings.today();
+    let (from, to) = match args.period {
+        Some(period) => period.range(today, settings.report.week_start),
+        None if args.heatmap => (
+            start_of_week(today, settings.report.week_start)
+                - chrono::Duration::weeks(args.weeks.saturating_sub(1) as i64),
+            today,
+        ),
+        None => (
+            start_of_week(today, settings.report.week_start) - chrono::Duration::weeks(1),
+            today,
+        ),
+    };
+
+    let mut timers = if args.offline {
+        EntryCache::load(config_dir, account)?.entries(from, to)
+    } else if args.team {
+        let tracker = auth::validated_credentials(config_dir, settings, account)
+            .await?
+            .tracker();
+        let mut timers = Vec::new();
+        for user_id in tracker.user_ids().await? {
+            timers.extend(tracker.user_time_entries(user_id, from, to).await?);
+        }
+        timers
+    } else {
+        match fetch_time_entries(config_dir, settings, account, from, to).await {
+            Ok(timers) => {
+                let mut cache = EntryCache::load(config_dir, account)?;
+                cache.store(from, to, &timers);
+                cache.save(config_dir, account)?;
+                timers
+            }
+            Err(err) if api::is_unreachable(&err) => {
+                eprintln!("couldn't reach harvest: {:#}", err);
+                EntryCache::load(config_dir, account)?.entries(from, to)
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    if let Some(query) = &args.project {
+        let project_id = match_project(settings, &timers, query)?;
+        timers.retain(|t| t.project.id == project_id);
+    }
+    if let Some(tag) = &args.tag {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        timers.retain(|t| tags(t.notes.as_deref().unwrap_or_default()).contains(&tag));
+    }
+    if args.billable || args.non_billable {
+        timers.retain(|t| t.billable == args.billable);
+    }
+    if args.period == Some(Period::Today) {
+        // oldest first, so the running total adds up down the page
+        timers.sort_by_key(|a| a.created_at);
+    }
+
+    if args.summary {
+        return match args.format {
+            ReportFormat::Text => print_weekly_summary(settings, &timers),
+            _ => Err(anyhow!("--summary only applies to the text format")),
+        };
+    }
+    if args.utilization {
+        return match args.format {
+            ReportFormat::Text => print_utilization(settings, &timers),
+            _ => Err(anyhow!("--utilization only applies to the text format")),
+        };
+    }
+
+    if args.audit {
+        return match args.format {
+            ReportFormat::Text => print_audit(settings, &timers, args.max_hours),
+            _ => Err(anyhow!("--audit only applies to the text format")),
+        };
+    }
+    if args.gaps {
+        return match args.format {
+            ReportFormat::Text => print_gaps(settings, args.below, &timers, from, to.min(today)),
+            _ => Err(anyhow!("--gaps only applies to the text format")),
+        };
+    }
+    if args.heatmap {
+        return match args.format {
+            ReportFormat::Text => print_heatmap(settings, &timers, from, to),
+            _ => Err(anyhow!("--heatmap only applies to the text format")),
+        };
+    }
+
+    let amounts = args.amounts;
+    match (args.format, args.group_by) {
+        (ReportFormat::Text, Some(GroupBy::Project)) => {
+            print_subtotals(settings, &timers, amounts, |t| vec![t.project.name.clone()])
+        }
+        (ReportFormat::Text, Some(GroupBy::Day)) => print_by_day(settings, &timers, amounts),
+        (ReportFormat::Text, Some(GroupBy::Task)) => {
+            print_subtotals(settings, &timers, amounts, |t| vec![t.task.name.clone()])
+        }
+        (ReportFormat::Text, Some(GroupBy::Client)) => {
+            print_subtotals(settings, &timers, amounts, |t| {
+                // harvest embeds the project's client in each entry
+                vec![t
+                    .client
+                    .as_ref()
+                    .map(|c| c.name.clone())
+                    .unwrap_or_else(|| "(no client)".to_string())]
+            })
+        }
+        (ReportFormat::Text, Some(GroupBy::User)) => {
+            print_subtotals(settings, &timers, amounts, |t| vec![t.user.name.clone()])
+        }
+        (ReportFormat::Text, Some(GroupBy::Tag)) => {
+            print_subtotals(settings, &timers, amounts, |t| {
+                let tags = tags(t.notes.as_deref().unwrap_or_default());
+                if tags.is_empty() {
+                    vec!["(no tag)".to_string()]
+                } else {
+                    tags.into_iter().map(|tag| format!("#{}", tag)).collect()
+                }
+            })
+        }
+        (ReportFormat::Text, None) if args.team => print_team(&timers),
+        (ReportFormat::Text, None) => print_text(
+            config_dir,
+            settings,
+            &timers,
+            from,
+            args.period == Some(Period::Today),
+            &columns(settings, args),
+        ),
+        (_, Some(_)) => Err(anyhow!("--group-by only applies to the text format")),
+        (ReportFormat::Json, None) => {
+            let entries: Vec<ReportEntry> = timers.iter().map(ReportEntry::from).collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+            Ok(())
+        }
+        (ReportFormat::Markdown, None) => print_markdown(settings, &timers, amounts),
+        (ReportFormat::Html, None) => {
+            let html = html_report(settings, &timers, from, to, amounts);
+            match &args.out {
+                Some(path) => fs::write(path, html)
+                    .with_context(|| format!("failed to write {}", path.display())),
+                None => {
+                    print!("{}", html);
+                    Ok(())
+                }
+            }
+        }
+        (ReportFormat::Csv, None) => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            for timer in timers.iter() {
+                writer.serialize(ReportEntry::from(timer))?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+    }
+}
+
+/// the #tags in an entry's notes, lowercased and without the #, e.g. `review`
+/// and `oncall` from "#review of #oncall runbook"
+fn tags(notes: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in notes.split_whitespace() {
+        let Some(tag) = word.strip_prefix('#') else {
+            continue;
+        };
+        let tag: String = tag
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+            .collect::<String>()
+            .to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// the current user's entries between two dates, inclusive, from the tracker
+async fn fetch_time_entries(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Vec<TimeEntry>> {
+    let tracker = auth::validated_credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+    tracker.time_entries(from, to).await
+}
+
+/// the id of the project best matching `query`, which is either a harvest
+/// project id or part of a name from a project config or harvest
+fn match_project(settings: &Settings, timers: &[TimeEntry], query: &str) -> anyhow::Result<i32> {
+    let configured = project::known_projects(settings)
+        .into_iter()
+        .map(|p| (p.name, p.harvest_project_id));
+    let from_harvest = timers
+        .iter()
+        .map(|t| (t.project.name.clone(), t.project.id));
+    let candidates: Vec<(String, i32)> = configured.chain(from_harvest).collect();
+
+    if let Some((_, id)) = candidates.iter().find(|(_, id)| id.to_string() == query) {
+        return Ok(*id);
+    }
+
+    let matcher = SkimMatcherV2::default();
+    candidates
+        .iter()
+        .filter_map(|(name, id)| Some((matcher.fuzzy_match(name, query)?, *id)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, id)| id)
+        .ok_or_else(|| anyhow!("no project matches {:?}", query))
+}
+
+fn print_text(
+    config_dir: &Path,
+    settings: &Settings,
+    timers: &[TimeEntry],
+    since: NaiveDate,
+    running_total: bool,
+    columns: &[Column],
+) -> anyhow::Result<()> {
+    // loops through all the timers and prints them using the tabwriter crate
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    let mut total = 0.0;
+
+    for timer in timers {
+        total += timer.current_hours();
+        if running_total {
+            write!(&mut tw, "{}\t", decimal_hours_to_string(total))?;
+        }
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| column.value(settings, timer))
+            .collect();
+        writeln!(&mut tw, "{}{}", running_marker(timer), cells.join("\t"))?;
+    }
+    if columns.contains(&Column::Amount) {
+        if running_total {
+            write!(&mut tw, "\t")?;
+        }
+        let cells: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| match column {
+                Column::Hours => {
+                    decimal_hours_to_string(timers.iter().map(TimeEntry::current_hours).sum())
+                }
+                Column::Amount => format_amount(settings, timers.iter().map(amount).sum()),
+                _ if i == 0 => "Total".to_string(),
+                _ => String::new(),
+            })
+            .collect();
+        writeln!(&mut tw, "  {}", cells.join("\t"))?;
+    }
+
+    tw.flush()?;
+
+    // progress against daily targets, for each day with entries
+    let projects = project::known_projects(settings);
+    let days = days(timers);
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout());
+    for day in days {
+        let entries: Vec<_> = timers
+            .iter()
+            .filter(|t| t.spent_date == day)
+            .cloned()
+            .collect();
+        for progress in targets::daily_progress(settings, &projects, &entries, &day.to_string()) {
+            writeln!(&mut tw, "{}", progress)?;
+        }
+    }
+    tw.flush()?;
+
+    let pomodoros = State::load(config_dir)?.pomodoros;
+    for (day, count) in pomodoros.range(since..) {
+        println!("{}\t{} pomodoro(s)", day, count);
+    }
+
+    Ok(())
+}
+
+/// what a billable entry earns at its billable rate, nothing for the rest
+fn amount(entry: &TimeEntry) -> f32 {
+    if entry.billable {
+        entry.current_hours() * entry.billable_rate.unwrap_or_default()
+    } else {
+        0.0
+    }
+}
+
+/// an amount in `[report] currency_format`
+pub fn format_amount(settings: &Settings, amount: f32) -> String {
+    settings
+        .report
+        .currency_format
+        .replace("{}", &format!("{:.2}", amount))
+}
+
+/// the amount followed by a tab when `--amounts` is passed, so the column
+/// disappears otherwise
+fn amount_column(settings: &Settings, amounts: bool, amount: f32) -> String {
+    if amounts {
+        format!("{}\t", format_amount(settings, amount))
+    } else {
+        String::new()
+    }
+}
+
+/// prints entries as a markdown table with a total row
+fn print_markdown(settings: &Settings, timers: &[TimeEntry], amounts: bool) -> anyhow::Result<()> {
+    let amount_header = if amounts { " Amount |" } else { "" };
+    let amount_rule = if amounts { " ---: |" } else { "" };
+    println!("| Date | Project | Task | Hours |{} Notes |", amount_header);
+    println!("| --- | --- | --- | ---: |{} --- |", amount_rule);
+
+    for timer in timers {
+        let amount = if amounts {
+            format!(" {} |", format_amount(settings, amount(timer)))
+        } else {
+            String::new()
+        };
+        println!(
+            "| {} | {} | {} | {} |{} {} |",
+            timer.spent_date,
+            markdown_cell(&timer.project.name),
+            markdown_cell(&timer.task.name),
+            decimal_hours_to_string(timer.current_hours()).trim(),
+            amount,
+            markdown_cell(timer.notes.as_deref().unwrap_or_default())
+        );
+    }
+
+    let total_amount = if amounts {
+        format!(
+            " **{}** |",
+            format_amount(settings, timers.iter().map(amount).sum())
+        )
+    } else {
+        String::new()
+    };
+    println!(
+        "| **Total** | | | **{}** |{} |",
+        decimal_hours_to_string(timers.iter().map(TimeEntry::current_hours).sum()).trim(),
+        total_amount
+    );
+
+    Ok(())
+}
+
+/// a standalone html page with a table of entries that sorts by any column
+/// when its heading is clicked, and a bar of hours for each project
+fn html_report(
+    settings: &Settings,
+    timers: &[TimeEntry],
+    from: NaiveDate,
+    to: NaiveDate,
+    amounts: bool,
+) -> String {
+    let mut projects: Vec<(String, f32)> = Vec::new();
+    for timer in timers {
+        let name = timer.project.name.clone();
+        let hours = timer.current_hours();
+        match projects.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, total)) => *total += hours,
+            None => projects.push((name, hours)),
+        }
+    }
+    projects.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let most = projects.first().map_or(0.0, |(_, hours)| *hours);
+
+    let chart: String = projects
+        .iter()
+        .map(|(name, hours)| {
+            format!(
+                "<tr><td>{}</td><td><div class=\"bar\" style=\"width: {:.0}%\"></div></td><td class=\"num\">{:.2}</td></tr>\n",
+                html_escape(name),
+                if most > 0.0 { hours / most * 100.0 } else { 0.0 },
+                hours
+            )
+        })
+        .collect();
+
+    let amount_header = if amounts { "<th>Amount</th>" } else { "" };
+    let rows: String = timers
+        .iter()
+        .map(|timer| {
+            let amount_cell = if amounts {
+                format!(
+                    "<td class=\"num\" data-sort=\"{:.2}\">{}</td>",
+                    amount(timer),
+                    html_escape(&format_amount(settings, amount(timer)))
+                )
+            } else {
+                String::new()
+            };
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{:.2}</td>{}<td>{}</td></tr>\n",
+                html_escape(&timer.spent_date.to_string()),
+                html_escape(&timer.project.name),
+                html_escape(&timer.task.name),
+                timer.current_hours(),
+                amount_cell,
+                html_escape(timer.notes.as_deref().unwrap_or_default())
+            )
+        })
+        .collect();
+
+    format!(
+        indoc! {r##"
+            <!doctype html>
+            <html>
+            <head>
+            <meta charset="utf-8">
+            <title>Time from {from} to {to}</title>
+            <style>
+              body {{ font-family: system-ui, sans-serif; margin: 2em; color: #222; }}
+              table {{ border-collapse: collapse; margin-bottom: 2em; }}
+              th, td {{ padding: 0.3em 0.8em; text-align: left; border-bottom: 1px solid #ddd; vertical-align: top; }}
+              th {{ cursor: pointer; user-select: none; }}
+              .num {{ text-align: right; font-variant-numeric: tabular-nums; }}
+              .chart td:nth-child(2) {{ width: 20em; }}
+              .bar {{ background: #4a90d9; height: 1em; }}
+            </style>
+            </head>
+            <body>
+            <h1>Time from {from} to {to}</h1>
+            <p>{total:.2} hours in {count} entries</p>
+            <h2>Projects</h2>
+            <table class="chart">
+            {chart}</table>
+            <h2>Entries</h2>
+            <table id="entries">
+            <thead><tr><th>Date</th><th>Project</th><th>Task</th><th>Hours</th>{amount_header}<th>Notes</th></tr></thead>
+            <tbody>
+            {rows}</tbody>
+            </table>
+            <script>
+              // sorts the entries by a column when its heading is clicked, again to reverse
+              document.querySelectorAll("#entries th").forEach((th, column) => {{
+                th.addEventListener("click", () => {{
+                  const body = document.querySelector("#entries tbody");
+                  const ascending = th.dataset.order !== "asc";
+                  th.dataset.order = ascending ? "asc" : "desc";
+                  const value = (row) => {{
+                    const cell = row.children[column];
+                    const text = cell.dataset.sort ?? cell.textContent;
+                    return cell.classList.contains("num") ? parseFloat(text) : text;
+                  }};
+                  [...body.rows]
+                    .sort((a, b) => (value(a) > value(b) ? 1 : value(a) < value(b) ? -1 : 0) * (ascending ? 1 : -1))
+                    .forEach((row) => body.appendChild(row));
+                }});
+              }});
+            </script>
+            </body>
+            </html>
+        "##},
+        from = from,
+        to = to,
+        total = timers.iter().map(TimeEntry::current_hours).sum::<f32>(),
+        count = timers.len(),
+        chart = chart,
+        amount_header = amount_header,
+        rows = rows,
+    )
+}
+
+/// text that's safe inside html elements and attributes
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// text that's safe inside a markdown table cell, with pipes escaped and
+/// newlines turned into spaces
+fn markdown_cell(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
+
+/// `▶` for a running entry, so it stands out from the rest
+fn running_marker(entry: &TimeEntry) -> &'static str {
+    if entry.is_running {
+        "▶ "
+    } else {
+        "  "
+    }
+}
+
+/// prints total hours and entry counts for each group of timers, with a grand
+/// total, and the amounts they earn with `--amounts`. an entry can be in more
+/// than one group, e.g. with several tags, but only counts once in the total
+fn print_subtotals(
+    settings: &Settings,
+    timers: &[TimeEntry],
+    amounts: bool,
+    keys: impl Fn(&TimeEntry) -> Vec<String>,
+) -> anyhow::Result<()> {
+    // groups stay in order of their most recent entry
+    let mut groups: Vec<(String, usize, f32, f32)> = Vec::new();
+    for timer in timers {
+        let hours = timer.current_hours();
+        for key in keys(timer) {
+            match groups.iter_mut().find(|(k, _, _, _)| *k == key) {
+                Some((_, count, total, total_amount)) => {
+                    *count += 1;
+                    *total += hours;
+                    *total_amount += amount(timer);
+                }
+                None => groups.push((key, 1, hours, amount(timer))),
+            }
+        }
+    }
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout());
+    for (key, count, hours, group_amount) in groups.iter() {
+        writeln!(
+            &mut tw,
+            "{}\t{} {}\t{}\t{}",
+            strip_newlines_and_tabs(key),
+            count,
+            if *count == 1 { "entry" } else { "entries" },
+            decimal_hours_to_string(*hours),
+            amount_column(settings, amounts, *group_amount)
+        )?;
+    }
+    writeln!(
+        &mut tw,
+        "Total\t{} entries\t{}\t{}",
+        timers.len(),
+        decimal_hours_to_string(timers.iter().map(TimeEntry::current_hours).sum()),
+        amount_column(settings, amounts, timers.iter().map(amount).sum())
+    )?;
+    tw.flush()?;
+
+    Ok(())
+}
+
+/// prints hours per person, then a table of hours with a row per project and a
+/// column per person
+fn print_team(timers: &[TimeEntry]) -> anyhow::Result<()> {
+    let mut people: Vec<String> = Vec::new();
+    let mut projects: Vec<String> = Vec::new();
+    for timer in timers {
+        let person = timer.user.name.clone();
+        if !people.contains(&person) {
+            people.push(person);
+        }
+        let project = timer.project.name.clone();
+        if !projects.contains(&project) {
+            projects.push(project);
+        }
+    }
+    people.sort();
+
+    let hours_for = |person: Option<&str>, project: Option<&str>| -> f32 {
+        timers
+            .iter()
+            .filter(|t| person.is_none_or(|p| t.user.name == p))
+            .filter(|t| project.is_none_or(|p| t.project.name == p))
+            .map(TimeEntry::current_hours)
+            .sum()
+    };
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout());
+    writeln!(&mut tw, "Person\tTotal\tBillable")?;
+    for person in people.iter() {
+        let billable: f32 = timers
+            .iter()
+            .filter(|t| t.user.name == *person && t.billable)
+            .map(TimeEntry::current_hours)
+            .sum();
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}",
+            strip_newlines_and_tabs(person),
+            decimal_hours_to_string(hours_for(Some(person), None)),
+            decimal_hours_to_string(billable)
+        )?;
+    }
+    tw.flush()?;
+    println!();
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout());
+    write!(&mut tw, "Project")?;
+    for person in people.iter() {
+        write!(&mut tw, "\t{}", strip_newlines_and_tabs(person))?;
+    }
+    writeln!(&mut tw, "\tTotal")?;
+    for project in projects.iter() {
+        write!(&mut tw, "{}", strip_newlines_and_tabs(project))?;
+        for person in people.iter() {
+            write!(
+                &mut tw,
+                "\t{}",
+                decimal_hours_to_string(hours_for(Some(person), Some(project)))
+            )?;
+        }
+        writeln!(
+            &mut tw,
+            "\t{}",
+            decimal_hours_to_string(hours_for(None, Some(project)))
+        )?;
+    }
+    tw.flush()?;
+
+    Ok(())
+}
+
+/// each day with entries once, newest first as harvest lists them, however
+/// the entries are ordered
+fn days(timers: &[TimeEntry]) -> Vec<NaiveDate> {
+    let mut days: Vec<NaiveDate> = timers.iter().map(|t| t.spent_date).collect();
+    days.sort_by(|a, b| b.cmp(a));
+    days.dedup();
+    days
+}
+
+/// prints each day's entries beneath a heading for the day, then its total
+fn print_by_day(settings: &Settings, timers: &[TimeEntry], amounts: bool) -> anyhow::Result<()> {
+    let days = days(timers);
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    for (i, day) in days.into_iter().enumerate() {
+        let entries: Vec<&TimeEntry> = timers.iter().filter(|t| t.spent_date == day).collect();
+        let heading = day.format("%A %Y-%m-%d");
+
+        if i > 0 {
+            writeln!(&mut tw)?;
+        }
+        writeln!(&mut tw, "{}", heading)?;
+        for entry in entries.iter() {
+            writeln!(
+                &mut tw,
+                "{} {}\t{}\t{}\t{}{}",
+                running_marker(entry),
+                theme::project(
+                    &settings.theme,
+                    entry.project.id,
+                    &strip_newlines_and_tabs(&entry.project.name)
+                ),
+                strip_newlines_and_tabs(&entry.task.name),
+                decimal_hours_to_string(entry.current_hours()),
+                amount_column(settings, amounts, amount(entry)),
+                theme::notes(&settings.theme, entry.notes.as_deref(), 60)
+            )?;
+        }
+        writeln!(
+            &mut tw,
+            "  Total\t\t{}\t{}",
+            decimal_hours_to_string(entries.iter().map(|e| e.current_hours()).sum()),
+            amount_column(settings, amounts, entries.iter().map(|e| amount(e)).sum())
+        )?;
+    }
+    tw.flush()?;
+
+    Ok(())
+}
+
+/// prints total and billable hours for each ISO week, and how far they are
+/// from `[targets] weekly`
+fn print_weekly_summary(settings: &Settings, timers: &[TimeEntry]) -> anyhow::Result<()> {
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    writeln!(&mut tw, "Week\tTotal\tBillable\tTarget\tStatus")?;
+    for ((year, week), total, billable) in weekly_totals(timers) {
+        let delta = match settings.targets.weekly {
+            Some(target) if total < target => {
+                format!("-{}", decimal_hours_to_string(target - total).trim())
+                    .red()
+                    .to_string()
+            }
+            Some(target) => format!("+{}", decimal_hours_to_string(total - target).trim()),
+            None => String::new(),
+        };
+        writeln!(
+            &mut tw,
+            "{}-W{:02}\t{}\t{}\t{}\t{}",
+            year,
+            week,
+            decimal_hours_to_string(total),
+            decimal_hours_to_string(billable),
+            delta,
+            week_status(timers, (year, week))
+        )?;
+    }
+    tw.flush()?;
+
+    Ok(())
+}
+
+/// prints each ISO week's total and billable hours as percentages of
+/// `[targets] capacity`, against the `billable` target, with an arrow for how
+/// billable utilization moved since the week before
+fn print_utilization(settings: &Settings, timers: &[TimeEntry]) -> anyhow::Result<()> {
+    let capacity = settings
+        .targets
+        .capacity
+        .filter(|c| *c > 0.0)
+        .ok_or_else(|| anyhow!("--utilization needs `capacity` set in [targets]"))?;
+
+    // oldest first, so each week's trend compares it to the one above
+    let mut weeks = weekly_totals(timers);
+    weeks.reverse();
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    writeln!(&mut tw, "Week\tTotal\tUtilization\tBillable\tTrend")?;
+    let mut previous: Option<f32> = None;
+    for ((year, week), total, billable) in weeks {
+        let utilization = total / capacity * 100.0;
+        let billable_utilization = billable / capacity * 100.0;
+
+        let billable_column = format!("{:.0}%", billable_utilization);
+        let billable_column = match settings.targets.billable {
+            Some(target) if billable_utilization < target => billable_column.red().to_string(),
+            _ => billable_column,
+        };
+        let trend = match previous {
+            Some(p) if billable_utilization > p + 0.5 => "↑",
+            Some(p) if billable_utilization < p - 0.5 => "↓",
+            Some(_) => "→",
+            None => "",
+        };
+        previous = Some(billable_utilization);
+
+        writeln!(
+            &mut tw,
+            "{}-W{:02}\t{}\t{:.0}%\t{}\t{}",
+            year,
+            week,
+            decimal_hours_to_string(total),
+            utilization,
+            billable_column,
+            trend
+        )?;
+    }
+    tw.flush()?;
+
+    Ok(())
+}
+
+/// prints each entry with something a timesheet reviewer would question, and
+/// what it is
+fn print_audit(settings: &Settings, timers: &[TimeEntry], max_hours: f32) -> anyhow::Result<()> {
+    let problems = audit(timers, max_hours);
+    if problems.is_empty() {
+        println!("Nothing to flag in {} entries", timers.len());
+        return Ok(());
+    }
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    for (timer, problem) in problems {
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}\t{}\t{}",
+            theme::date(&settings.theme, &timer.spent_date.to_string()),
+            strip_newlines_and_tabs(&timer.project.name),
+            decimal_hours_to_string(timer.current_hours()),
+            theme::notes(&settings.theme, timer.notes.as_deref(), 40),
+            problem.red()
+        )?;
+    }
+    tw.flush()?;
+
+    Ok(())
+}
+
+/// the entries `--audit` flags and why: no notes, more than `max_hours`,
+/// overlapping another or repeating another's project and notes
+fn audit(timers: &[TimeEntry], max_hours: f32) -> Vec<(&TimeEntry, String)> {
+    let mut problems: Vec<(&TimeEntry, String)> = Vec::new();
+
+    for (i, timer) in timers.iter().enumerate() {
+        let hours = timer.current_hours();
+        if timer.notes.as_deref().is_none_or(|n| n.trim().is_empty()) {
+            problems.push((timer, "no notes".to_string()));
+        }
+        if hours > max_hours {
+            problems.push((
+                timer,
+                format!(
+                    "longer than {} hours",
+                    decimal_hours_to_string(max_hours).trim()
+                ),
+            ));
+        }
+
+        // each pair is compared once, from its first entry
+        for other in timers[i + 1..].iter() {
+            if other.spent_date != timer.spent_date {
+                continue;
+            }
+            if let (Some(a), Some(b)) = (entry_span(timer), entry_span(other)) {
+                if a.0 < b.1 && b.0 < a.1 {
+                    let description = format!(
+                        "overlaps {} from {} to {}",
+                        other.project.name,
+                        b.0.format("%H:%M"),
+                        b.1.format("%H:%M")
+                    );
+                    problems.push((timer, description));
+                }
+            }
+            let notes = timer.notes.as_deref().map(str::trim).unwrap_or_default();
+            if !notes.is_empty()
+                && other.notes.as_deref().map(str::trim) == Some(notes)
+                && other.project.id == timer.project.id
+            {
+                problems.push((timer, "same project and notes as another entry".to_string()));
+            }
+        }
+    }
+
+    problems
+}
+
+/// when an entry started and ended, for accounts that track start and end
+/// times
+pub fn entry_span(entry: &TimeEntry) -> Option<(NaiveTime, NaiveTime)> {
+    let started = timer::parse_time(entry.started_time.as_deref()?).ok()?;
+    let ended = timer::parse_time(entry.ended_time.as_deref()?).ok()?;
+    Some((started, ended))
+}
+
+/// workdays from `from` up to but not including `until` with fewer hours than
+/// `threshold`, and the hours they have. weekends and `holidays` aren't
+/// workdays
+pub fn short_days(
+    settings: &Settings,
+    threshold: f32,
+    timers: &[TimeEntry],
+    from: NaiveDate,
+    until: NaiveDate,
+) -> Vec<(NaiveDate, f32)> {
+    from.iter_days()
+        .take_while(|d| *d < until)
+        .filter(|day| {
+            !matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+                && !settings.report.holidays.contains(day)
+        })
+        .map(|day| {
+            let hours: f32 = timers
+                .iter()
+                .filter(|t| t.spent_date == day)
+                .map(TimeEntry::current_hours)
+                .sum();
+            (day, hours)
+        })
+        .filter(|(_, hours)| *hours < threshold)
+        .collect()
+}
+
+/// prints each workday from `from` up to but not including `until` with fewer
+/// hours than `below`, and how many it's short
+fn print_gaps(
+    settings: &Settings,
+    below: Option<f32>,
+    timers: &[TimeEntry],
+    from: NaiveDate,
+    until: NaiveDate,
+) -> anyhow::Result<()> {
+    let threshold = below.or(settings.targets.daily).unwrap_or(8.0);
+
+    let mut tw = tabwriter::TabWriter::new(io::stdout()).ansi(true);
+    let short = short_days(settings, threshold, timers, from, until);
+    for (day, hours) in short.iter().copied() {
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}",
+            day.format("%a %Y-%m-%d"),
+            decimal_hours_to_string(hours),
+            format!("-{}", decimal_hours_to_string(threshold - hours).trim()).red()
+        )?;
+    }
+    tw.flush()?;
+
+    if short.is_empty() {
+        println!(
+            "Every workday has at least {} hours",
+            decimal_hours_to_string(threshold).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// prints a row per day of the week and a column per week, each day shaded by
+/// its hours as a share of `[targets] daily`, or 8 hours
+fn print_heatmap(
+    settings: &Settings,
+    timers: &[TimeEntry],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<()> {
+    const SHADES: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+    let target = settings.targets.daily.filter(|t| *t > 0.0).unwrap_or(8.0);
+
+    let hours_on = |day: NaiveDate| -> f32 {
+        timers
+            .iter()
+            .filter(|t| t.spent_date == day)
+            .map(TimeEntry::current_hours)
+            .sum()
+    };
+
+    let weeks = (to - from).num_weeks() + 1;
+    for weekday in 0..7 {
+        let first = from + chrono::Duration::days(weekday);
+        print!("{} ", first.format("%a"));
+        for week in 0..weeks {
+            let day = first + chrono::Duration::weeks(week);
+            if day > to {
+                break;
+            }
+            let hours = hours_on(day);
+            let shade = if hours <= 0.0 {
+                0
+            } else {
+                // anything tracked gets at least the lightest block
+                ((hours / target * 4.0).ceil() as usize).clamp(1, 4)
+            };
+            print!("{}", SHADES[shade]);
+        }
+        println!();
+    }
+    println!(
+        "    {} none, {} up to {}, {} the daily target",
+        SHADES[0],
+        SHADES[1],
+        decimal_hours_to_string(target / 4.0).trim(),
+        SHADES[4]
+    );
+
+    Ok(())
+}
+
+/// the least advanced approval status of a week's entries, or "open" when
+/// any can still be edited
+fn week_status(timers: &[TimeEntry], week: (i32, u32)) -> &'static str {
+    let statuses: Vec<&str> = timers
+        .iter()
+        .filter(|t| {
+            let date = t.spent_date;
+            (date.iso_week().year(), date.iso_week().week()) == week
+        })
+        .map(TimeEntry::status)
+        .collect();
+
+    ["", "locked", "submitted", "approved"]
+        .into_iter()
+        .find(|status| statuses.contains(status))
+        .map(|status| if status.is_empty() { "open" } else { status })
+        .unwrap_or("open")
+}
+
+/// total and billable hours for each ISO week with entries, in order of the
+/// latest week
+fn weekly_totals(timers: &[TimeEntry]) -> Vec<((i32, u32), f32, f32)> {
+    let mut weeks: Vec<((i32, u32), f32, f32)> = Vec::new();
+    for timer in timers {
+        let date = timer.spent_date;
+        let week = (date.iso_week().year(), date.iso_week().week());
+        let hours = timer.current_hours();
+        let billable = if timer.billable { hours } else { 0.0 };
+
+        match weeks.iter_mut().find(|(w, _, _)| *w == week) {
+            Some((_, total, total_billable)) => {
+                *total += hours;
+                *total_billable += billable;
+            }
+            None => weeks.push((week, hours, billable)),
+        }
+    }
+
+    weeks
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn entry(date: &str, hours: f32) -> TimeEntry {
+        serde_json::from_value(json!({
+            "id": 1,
+            "spent_date": date,
+            "hours": hours,
+            "project": { "id": 1, "name": "Acme" },
+            "task": { "id": 1 },
+            "user": { "id": 1 },
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_each_tag_once_lowercased() {
+        assert_eq!(tags("#review of #oncall runbook"), ["review", "oncall"]);
+        assert_eq!(tags("#Review, then #review again"), ["review"]);
+        assert_eq!(tags("#on-call_2 (#sync)"), ["on-call_2"]);
+        assert!(tags("issue # 12 and a#b").is_empty());
+    }
+
+    #[test]
+    fn keeps_markdown_cells_on_one_line() {
+        assert_eq!(markdown_cell("a | b\nc\t d"), "a \\| b c d");
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            html_escape(r#"<a href="x">Q&A</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Q&amp;A&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn skips_weekends_and_holidays_when_finding_short_days() {
+        let mut settings = Settings::default();
+        settings.report.holidays = vec![date("2024-01-03")];
+        // monday the 1st to sunday the 7th
+        let timers = [entry("2024-01-01", 8.0), entry("2024-01-02", 3.0)];
+
+        let short = short_days(
+            &settings,
+            8.0,
+            &timers,
+            date("2024-01-01"),
+            date("2024-01-08"),
+        );
+        assert_eq!(
+            short,
+            [
+                (date("2024-01-02"), 3.0),
+                (date("2024-01-04"), 0.0),
+                (date("2024-01-05"), 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn a_week_is_as_open_as_its_least_approved_entry() {
+        let week = (2024, 1);
+        let mut approved = entry("2024-01-01", 1.0);
+        approved.approval_status = Some("approved".to_string());
+        let mut submitted = entry("2024-01-02", 1.0);
+        submitted.approval_status = Some("submitted".to_string());
+        let open = entry("2024-01-03", 1.0);
+        // in the week after, so it doesn't count
+        let next_week = entry("2024-01-08", 1.0);
+
+        assert_eq!(week_status(&[approved.clone()], week), "approved");
+        assert_eq!(
+            week_status(&[approved.clone(), submitted.clone()], week),
+            "submitted"
+        );
+        assert_eq!(week_status(&[approved, submitted, open], week), "open");
+        assert_eq!(week_status(&[next_week], week), "open");
+    }
+
+    #[test]
+    fn totals_hours_and_billable_hours_by_iso_week() {
+        let mut billable = entry("2024-01-08", 2.0);
+        billable.billable = true;
+        // the 31st of december 2023 is in the last week of 2023, the 1st of
+        // january in the first of 2024
+        let timers = [
+            billable,
+            entry("2024-01-09", 1.0),
+            entry("2024-01-01", 4.0),
+            entry("2023-12-31", 0.5),
+        ];
+
+        assert_eq!(
+            weekly_totals(&timers),
+            [
+                ((2024, 2), 3.0, 2.0),
+                ((2024, 1), 4.0, 0.0),
+                ((2023, 52), 0.5, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_overlapping_entries_once_on_the_same_day() {
+        let span = |date: &str, started: &str, ended: &str| {
+            let mut entry = entry(date, 1.0);
+            entry.notes = Some(format!("from {}", started));
+            entry.started_time = Some(started.to_string());
+            entry.ended_time = Some(ended.to_string());
+            entry
+        };
+        let timers = [
+            span("2024-01-01", "9:00am", "10:30am"),
+            span("2024-01-01", "10:00am", "11:00am"),
+            // touching isn't overlapping
+            span("2024-01-01", "11:00am", "12:00pm"),
+            // the same times on another day
+            span("2024-01-02", "9:00am", "10:30am"),
+        ];
+
+        let problems: Vec<(&str, String)> = audit(&timers, 8.0)
+            .into_iter()
+            .map(|(timer, problem)| (timer.notes.as_deref().unwrap(), problem))
+            .collect();
+        assert_eq!(
+            problems,
+            [(
+                "from 9:00am",
+                "overlaps Acme from 10:00 to 11:00".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn lists_each_day_once_however_entries_are_ordered() {
+        let timers = [
+            entry("2024-01-01", 1.0),
+            entry("2024-01-02", 1.0),
+            entry("2024-01-01", 1.0),
+        ];
+        assert_eq!(days(&timers), [date("2024-01-02"), date("2024-01-01")]);
+    }
+}
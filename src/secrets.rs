@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, process::Command};
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+/// an external program the harvest token can be read from, configured under
+/// `[credentials]` in clockhand.toml, e.g.
+///
+/// ```toml
+/// [credentials]
+/// provider = "1password"
+/// reference = "op://Private/Harvest/token"
+/// account_id = 123456
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider", rename_all = "kebab-case")]
+pub enum SecretSource {
+    /// `op read <reference>`
+    #[serde(rename = "1password")]
+    OnePassword { reference: String },
+
+    /// `pass show <name>`, using the first line
+    Pass { name: String },
+
+    /// `secret-tool lookup <attribute> <value>...` from libsecret
+    SecretTool {
+        attributes: BTreeMap<String, String>,
+    },
+
+    /// any shell command that prints the token
+    Command { command: String },
+}
+
+impl SecretSource {
+    fn command(&self) -> Command {
+        match self {
+            SecretSource::OnePassword { reference } => {
+                let mut command = Command::new("op");
+                command.arg("read").arg(reference);
+                command
+            }
+            SecretSource::Pass { name } => {
+                let mut command = Command::new("pass");
+                command.arg("show").arg(name);
+                command
+            }
+            SecretSource::SecretTool { attributes } => {
+                let mut command = Command::new("secret-tool");
+                command.arg("lookup");
+                for (attribute, value) in attributes {
+                    command.arg(attribute).arg(value);
+                }
+                command
+            }
+            SecretSource::Command { command: line } => shell_command(line),
+        }
+    }
+
+    /// runs the provider and returns what it printed, trimmed
+    pub fn read(&self) -> anyhow::Result<String> {
+        let mut command = self.command();
+        let program = command.get_program().to_string_lossy().into_owned();
+
+        let output = command
+            .output()
+            .with_context(|| format!("failed to run {}, is it installed?", program))?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} exited with {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let secret = String::from_utf8(output.stdout)
+            .with_context(|| format!("{} printed something that isn't utf-8", program))?;
+
+        Ok(secret.trim().to_string())
+    }
+}
+
+/// a command that runs `line` through the platform's shell
+pub fn shell_command(line: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(line);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(line);
+        command
+    }
+}
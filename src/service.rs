@@ -0,0 +1,245 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Context};
+
+use crate::logging;
+
+/// launchd label, also the plist's file name
+const LAUNCHD_LABEL: &str = "com.schpet.clockhand.watch";
+
+/// file name of the systemd user unit
+const SYSTEMD_UNIT: &str = "clockhand-watch.service";
+
+/// runs `clockhand watch` at login as a launchd agent, or a systemd user
+/// service, with the same config directory, account and profile as this run,
+/// and starts it now
+pub fn install(
+    config_dir: &Path,
+    account: Option<&str>,
+    profile: Option<&str>,
+    interval: Option<u64>,
+    systemd: bool,
+) -> anyhow::Result<()> {
+    let arguments = watch_arguments(config_dir, account, profile, interval)?;
+    if systemd {
+        return install_systemd(&arguments);
+    }
+
+    let path = launchd_plist_path()?;
+    // watch logs to its own file, this catches anything it can't log there
+    let log_dir = logging::log_dir()?;
+    fs::create_dir_all(&log_dir)?;
+    let log = log_dir.join("watch.stderr.log");
+
+    if path.exists() {
+        // reloading picks up a new binary or arguments
+        launchctl(&["unload", "-w"], &path)?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, launchd_plist(&arguments, &log))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    launchctl(&["load", "-w"], &path)?;
+
+    println!("Installed {}", path.display());
+    println!(
+        "watch now runs at login, see what it's doing with `{} logs`",
+        env!("CARGO_PKG_NAME")
+    );
+    Ok(())
+}
+
+/// stops the launchd agent or systemd service and removes it
+pub fn uninstall() -> anyhow::Result<()> {
+    if let Some(path) = systemd_unit_path() {
+        if !path.exists() {
+            println!("watch isn't installed, there's no {}", path.display());
+            return Ok(());
+        }
+        systemctl(&["disable", "--now", SYSTEMD_UNIT])?;
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        systemctl(&["daemon-reload"])?;
+        println!("Uninstalled {}", path.display());
+        return Ok(());
+    }
+
+    let path = launchd_plist_path()?;
+    if !path.exists() {
+        println!("watch isn't installed, there's no {}", path.display());
+        return Ok(());
+    }
+
+    launchctl(&["unload", "-w"], &path)?;
+    fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+    println!("Uninstalled {}", path.display());
+    Ok(())
+}
+
+/// ~/Library/LaunchAgents/com.schpet.clockhand.watch.plist
+fn launchd_plist_path() -> anyhow::Result<PathBuf> {
+    if !cfg!(target_os = "macos") {
+        return Err(anyhow!("installing watch as a launchd agent needs macOS"));
+    }
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("couldn't determine the home directory"))?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+fn install_systemd(arguments: &[String]) -> anyhow::Result<()> {
+    let path = systemd_unit_path()
+        .ok_or_else(|| anyhow!("installing watch as a systemd service needs linux"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, systemd_unit(arguments))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    systemctl(&["daemon-reload"])?;
+    // restarting picks up a new binary or arguments when already running
+    systemctl(&["enable", SYSTEMD_UNIT])?;
+    systemctl(&["restart", SYSTEMD_UNIT])?;
+
+    println!("Installed {}", path.display());
+    println!(
+        "watch now runs at login, see its output with `journalctl --user -u {}`",
+        SYSTEMD_UNIT
+    );
+    Ok(())
+}
+
+/// ~/.config/systemd/user/clockhand-watch.service on linux
+fn systemd_unit_path() -> Option<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    Some(dirs::config_dir()?.join("systemd/user").join(SYSTEMD_UNIT))
+}
+
+/// this executable followed by the arguments to run watch the same way
+fn watch_arguments(
+    config_dir: &Path,
+    account: Option<&str>,
+    profile: Option<&str>,
+    interval: Option<u64>,
+) -> anyhow::Result<Vec<String>> {
+    let exe = std::env::current_exe().context("couldn't find the clockhand executable")?;
+    let mut arguments = vec![
+        exe.display().to_string(),
+        "--config-dir".to_string(),
+        config_dir.display().to_string(),
+    ];
+    if let Some(account) = account {
+        arguments.extend(["--account".to_string(), account.to_string()]);
+    }
+    if let Some(profile) = profile {
+        arguments.extend(["--profile".to_string(), profile.to_string()]);
+    }
+    arguments.push("watch".to_string());
+    if let Some(interval) = interval {
+        arguments.extend(["--interval".to_string(), interval.to_string()]);
+    }
+    Ok(arguments)
+}
+
+fn launchd_plist(arguments: &[String], log: &Path) -> String {
+    let arguments: String = arguments
+        .iter()
+        .map(|argument| format!("        <string>{}</string>\n", xml_escape(argument)))
+        .collect();
+    let log = xml_escape(&log.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = LAUNCHD_LABEL,
+        arguments = arguments,
+        log = log,
+    )
+}
+
+fn systemd_unit(arguments: &[String]) -> String {
+    let exec_start: Vec<String> = arguments.iter().map(|a| systemd_quote(a)).collect();
+    format!(
+        r#"[Unit]
+Description=clockhand watch, reminding about harvest timers
+After=graphical-session.target
+
+[Service]
+ExecStart={exec_start}
+Restart=on-failure
+RestartSec=10
+StandardOutput=journal
+StandardError=journal
+SyslogIdentifier=clockhand
+
+[Install]
+WantedBy=default.target
+"#,
+        exec_start = exec_start.join(" ")
+    )
+}
+
+/// quotes an ExecStart argument, escaping what systemd would otherwise expand
+fn systemd_quote(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+            .replace('$', "$$")
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn launchctl(arguments: &[&str], plist: &Path) -> anyhow::Result<()> {
+    let status = Command::new("launchctl")
+        .args(arguments)
+        .arg(plist)
+        .status()
+        .context("failed to run launchctl")?;
+    if !status.success() {
+        return Err(anyhow!(
+            "launchctl {} {} failed",
+            arguments.join(" "),
+            plist.display()
+        ));
+    }
+    Ok(())
+}
+
+fn systemctl(arguments: &[&str]) -> anyhow::Result<()> {
+    let status = Command::new("systemctl")
+        .arg("--user")
+        .args(arguments)
+        .status()
+        .context("failed to run systemctl")?;
+    if !status.success() {
+        return Err(anyhow!("systemctl --user {} failed", arguments.join(" ")));
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use anyhow::Context;
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+/// prints a script that tells a running `watch` about each command run, for
+/// the shell to eval, so work done only in a terminal counts as activity
+pub fn hook(config_dir: &Path, shell: Shell) -> anyhow::Result<()> {
+    let exe = std::env::current_exe().context("couldn't find the clockhand executable")?;
+    let command = [
+        exe.display().to_string(),
+        "--config-dir".to_string(),
+        config_dir.display().to_string(),
+        "watch".to_string(),
+        "activity".to_string(),
+    ]
+    .iter()
+    .map(|argument| quote(shell, argument))
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    // in the background and quietly, so a slow or stopped watch never holds up
+    // the prompt
+    let script = match shell {
+        Shell::Zsh => format!(
+            r#"_clockhand_activity() {{
+  ({command} "$PWD" >/dev/null 2>&1 &)
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec _clockhand_activity"#
+        ),
+        Shell::Bash => format!(
+            r#"_clockhand_activity() {{
+  ({command} "$PWD" >/dev/null 2>&1 &)
+}}
+if [[ ";${{PROMPT_COMMAND:-}};" != *";_clockhand_activity;"* ]]; then
+  PROMPT_COMMAND="_clockhand_activity${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi"#
+        ),
+        Shell::Fish => format!(
+            r#"function _clockhand_activity --on-event fish_preexec
+    {command} $PWD >/dev/null 2>&1 &
+    disown 2>/dev/null
+end"#
+        ),
+    };
+    println!("{}", script);
+    Ok(())
+}
+
+/// single quotes an argument the way the shell escapes quotes inside them
+fn quote(shell: Shell, argument: &str) -> String {
+    match shell {
+        Shell::Zsh | Shell::Bash => format!("'{}'", argument.replace('\'', r"'\''")),
+        Shell::Fish => format!("'{}'", argument.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
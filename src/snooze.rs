@@ -0,0 +1,78 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Local, Utc};
+
+use crate::{
+    config::Settings,
+    project::{self, Project},
+    state::State,
+};
+
+/// quiets `watch` reminders for a project, the current directory's by default,
+/// or lets them resume with `clear`
+pub fn snooze(
+    config_dir: &Path,
+    settings: &Settings,
+    project: Option<&str>,
+    hours: f32,
+    clear: bool,
+) -> anyhow::Result<()> {
+    let project = match project {
+        Some(query) => match_project(settings, query)?,
+        None => project::current_project()?,
+    };
+
+    if clear {
+        let mut state = State::load(config_dir)?;
+        state.snoozed.remove(&project.harvest_project_id);
+        state.save(config_dir)?;
+        println!("Reminders for {} are back on", project.name);
+        return Ok(());
+    }
+
+    let until = snooze_project(
+        config_dir,
+        &project,
+        Duration::from_secs_f32(hours * 60.0 * 60.0),
+    )?;
+    println!(
+        "Snoozed reminders for {} until {}",
+        project.name,
+        until.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
+
+/// records that reminders for a project wait until `duration` from now,
+/// returning when that is
+pub fn snooze_project(
+    config_dir: &Path,
+    project: &Project,
+    duration: Duration,
+) -> anyhow::Result<DateTime<Utc>> {
+    let until = Utc::now() + chrono::Duration::from_std(duration)?;
+    let mut state = State::load(config_dir)?;
+    state.snoozed.insert(project.harvest_project_id, until);
+    // forget snoozes that have run out
+    let now = Utc::now();
+    state.snoozed.retain(|_, until| *until > now);
+    state.save(config_dir)?;
+    Ok(until)
+}
+
+/// a known project with this harvest id or name, ignoring case
+fn match_project(settings: &Settings, query: &str) -> anyhow::Result<Project> {
+    project::known_projects(settings)
+        .into_iter()
+        .find(|p| {
+            p.harvest_project_id.to_string() == query
+                || p.name.to_lowercase() == query.to_lowercase()
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "no project named {:?} in the current directory or watch.projects",
+                query
+            )
+        })
+}
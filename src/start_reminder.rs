@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{Datelike, Local, NaiveDate, NaiveTime};
+
+use crate::{
+    config::Settings, control::Control, day_summary, logging, notification, project::Project, timer,
+};
+
+/// when to remind on `day` that no timer's been started, from `start_reminder`
+/// in [watch]. None on weekdays without a time and on holidays
+pub fn reminder_time(settings: &Settings, day: NaiveDate) -> anyhow::Result<Option<NaiveTime>> {
+    if settings.report.holidays.contains(&day) {
+        return Ok(None);
+    }
+    settings
+        .watch
+        .start_reminder
+        .get(&day.weekday())
+        .map(|at| {
+            timer::parse_time(at)
+                .with_context(|| format!("bad {} time in start_reminder in [watch]", day.weekday()))
+        })
+        .transpose()
+}
+
+/// reminds that no timer's running yet, unless there's already an entry today
+/// in one of the projects' accounts
+pub async fn remind_if_not_started(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    projects: &[Project],
+    control: &Control,
+) -> anyhow::Result<()> {
+    let today = settings.today();
+    let entries =
+        day_summary::time_entries(config_dir, settings, account, projects, today, today).await?;
+    if !entries.is_empty() {
+        return Ok(());
+    }
+
+    let summary = format!(
+        "It's {} and no timer is running yet",
+        settings.time(Local::now()).format("%-H:%M")
+    );
+    let body = format!("Start one with `{} start`", env!("CARGO_PKG_NAME"));
+    logging::info!("{}", summary);
+    notification::show(&summary, &body, &settings.notifications.sound)?;
+    control.record_notification(&summary, &body);
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::encryption;
+
+/// things clockhand remembers between runs, kept in state.json in the config
+/// directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// harvest project id to the id of the task last used for it
+    pub last_tasks: BTreeMap<i32, i32>,
+
+    /// pomodoros completed each day
+    pub pomodoros: BTreeMap<NaiveDate, u32>,
+
+    /// harvest project id to when `watch` can remind about it again
+    pub snoozed: BTreeMap<i32, DateTime<Utc>>,
+
+    /// what `watch` was keeping track of when it was last stopped, for the
+    /// next one to carry on with
+    pub watch: WatchState,
+}
+
+/// a stopped watch's pause and counts for each project, by config path
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchState {
+    pub stopped_at: Option<DateTime<Local>>,
+    pub paused: bool,
+    pub projects: BTreeMap<PathBuf, WatchedProjectState>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchedProjectState {
+    /// when a file in it last changed
+    pub last_event: Option<DateTime<Local>>,
+
+    /// seconds of activity since a timer was last seen running for it
+    pub untracked_seconds: i64,
+
+    /// reminders ignored in a row, for escalation
+    pub ignored_reminders: usize,
+}
+
+impl State {
+    fn path(config_dir: &Path) -> std::path::PathBuf {
+        config_dir.join("state.json")
+    }
+
+    pub fn load(config_dir: &Path) -> anyhow::Result<State> {
+        let path = State::path(config_dir);
+        match encryption::read_to_string(&path)? {
+            Some(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse {}", path.display())),
+            None => Ok(State::default()),
+        }
+    }
+
+    /// when reminders for a project resume, if they're snoozed
+    pub fn snoozed_until(&self, project_id: i32) -> Option<DateTime<Utc>> {
+        self.snoozed
+            .get(&project_id)
+            .copied()
+            .filter(|until| *until > Utc::now())
+    }
+
+    pub fn save(&self, config_dir: &Path) -> anyhow::Result<()> {
+        encryption::write(
+            &State::path(config_dir),
+            &serde_json::to_string_pretty(self)?,
+        )
+    }
+}
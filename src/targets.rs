@@ -0,0 +1,79 @@
+use std::fmt;
+
+use crate::{config::Settings, decimal_hours_to_string, project::Project, tracker::TimeEntry};
+
+const BAR_WIDTH: usize = 20;
+
+/// hours tracked in a day against a target
+#[derive(Debug, Clone)]
+pub struct Progress {
+    /// None for the overall target
+    pub project_id: Option<i32>,
+    pub label: String,
+    pub hours: f32,
+    pub target: f32,
+}
+
+impl Progress {
+    pub fn reached(&self) -> bool {
+        self.hours >= self.target
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let filled = ((self.hours / self.target).min(1.0) * BAR_WIDTH as f32).round() as usize;
+        write!(
+            f,
+            "{}\t[{}{}]\t{} of {}",
+            self.label,
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            decimal_hours_to_string(self.hours).trim(),
+            decimal_hours_to_string(self.target).trim()
+        )
+    }
+}
+
+/// progress of one day's entries against the overall target, labelled
+/// `label`, and the targets of any projects they're for
+pub fn daily_progress(
+    settings: &Settings,
+    projects: &[Project],
+    entries: &[TimeEntry],
+    label: &str,
+) -> Vec<Progress> {
+    let hours_for = |project_id: Option<i32>| -> f32 {
+        entries
+            .iter()
+            .filter(|e| project_id.is_none() || Some(e.project.id) == project_id)
+            .map(TimeEntry::current_hours)
+            .sum()
+    };
+
+    let overall = settings.targets.daily.map(|target| Progress {
+        project_id: None,
+        label: label.to_string(),
+        hours: hours_for(None),
+        target,
+    });
+
+    let mut seen = Vec::new();
+    let per_project = projects.iter().filter_map(|project| {
+        let target = project.daily_target?;
+        // a project can be both the current one and a watched one
+        if seen.contains(&project.harvest_project_id) {
+            return None;
+        }
+        seen.push(project.harvest_project_id);
+
+        Some(Progress {
+            project_id: Some(project.harvest_project_id),
+            label: project.name.clone(),
+            hours: hours_for(Some(project.harvest_project_id)),
+            target,
+        })
+    });
+
+    overall.into_iter().chain(per_project).collect()
+}
@@ -0,0 +1,42 @@
+use crate::config::Templates;
+
+/// the reminder's wording when neither clockhand.toml nor the project sets one
+///
+/// variables are:
+/// - `project`: the project's name
+/// - `branch`: the git branch checked out in its root, or nothing
+/// - `project_and_branch`: the name followed by the branch in parentheses
+/// - `status`: "Timer not running" or "Timer running for other project"
+/// - `untracked_minutes`: activity without a timer since one last ran
+/// - `timer_project` and `timer_duration`: the other project's running timer
+pub fn default_templates() -> Templates {
+    Templates {
+        summary: Some("{{status}}".to_string()),
+        body: Some("Start a timer for {{project_and_branch}}".to_string()),
+        untracked_body: Some(
+            "You've been active {{untracked_minutes}} min without a timer, start one for {{project_and_branch}}?"
+                .to_string(),
+        ),
+    }
+}
+
+/// replaces each `{{name}}` with its value, ignoring spaces inside the braces
+/// as handlebars does. unknown names become nothing, and braces that aren't
+/// closed are left as they are
+pub fn render(template: &str, variables: &[(&str, String)]) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let name = rest[start + 2..start + end].trim();
+        if let Some((_, value)) = variables.iter().find(|(n, _)| *n == name) {
+            rendered.push_str(value);
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
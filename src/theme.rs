@@ -0,0 +1,58 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use colored::{Color, ColoredString, Colorize};
+
+use crate::{config::ThemeSettings, truncate_with_ellipsis};
+
+/// turns colors off for `--no-color`, `NO_COLOR` or when stdout isn't a
+/// terminal, unless `CLICOLOR_FORCE` is set
+pub fn configure(no_color: bool) {
+    let forced = env::var("CLICOLOR_FORCE").is_ok_and(|v| v != "0");
+    let no_color_env = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+
+    if no_color || no_color_env || (!forced && !io::stdout().is_terminal()) {
+        colored::control::set_override(false);
+    }
+}
+
+/// a color name from the theme, e.g. "cyan" or "bright black", ignoring ones
+/// `colored` doesn't know
+fn color(name: &str) -> Option<Color> {
+    name.parse().ok()
+}
+
+/// a date in the theme's `dates` color, dimmed by default
+pub fn date(theme: &ThemeSettings, date: &str) -> ColoredString {
+    match theme.dates.as_deref().and_then(color) {
+        Some(c) => date.color(c),
+        None => date.dimmed(),
+    }
+}
+
+/// a project's name in a color picked from the theme's `projects` by its id,
+/// so a project keeps its color from one report to the next
+pub fn project(theme: &ThemeSettings, project_id: i32, name: &str) -> ColoredString {
+    let picked = match theme.projects.len() {
+        0 => None,
+        n => color(&theme.projects[project_id.unsigned_abs() as usize % n]),
+    };
+    match picked {
+        Some(c) => name.color(c),
+        None => name.normal(),
+    }
+}
+
+/// an entry's notes cut to `max` characters, or `(none)` in the theme's
+/// `empty_notes` color when there aren't any
+pub fn notes(theme: &ThemeSettings, notes: Option<&str>, max: usize) -> ColoredString {
+    match notes.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(notes) => truncate_with_ellipsis(notes, max).normal(),
+        None => match color(&theme.empty_notes) {
+            Some(c) => "(none)".color(c),
+            None => "(none)".normal(),
+        },
+    }
+}
@@ -0,0 +1,1039 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime};
+
+use crate::{
+    auth,
+    config::{Alias, Settings},
+    control, decimal_hours_to_string,
+    project::{current_project, find_project, known_projects, Project},
+    prompt,
+    state::State,
+    strip_newlines_and_tabs, targets,
+    tracker::{
+        find_project_assignment, EntryUpdate, ProjectAssignment, TaskAssignment, TimeEntry,
+        TimeTracker,
+    },
+    truncate_with_ellipsis,
+};
+
+/// what a new time entry is for, from flags and possibly an alias
+#[derive(Debug, Default)]
+pub struct EntryTarget {
+    /// harvest project name or id, defaults to the current directory's
+    pub project: Option<String>,
+    pub task: Option<String>,
+    pub billable: Option<bool>,
+    pub notes: Option<String>,
+    pub hours: Option<f32>,
+}
+
+impl EntryTarget {
+    /// fills in whatever wasn't passed on the command line from an alias
+    pub fn or_alias(self, alias: &Alias) -> anyhow::Result<EntryTarget> {
+        Ok(EntryTarget {
+            project: self
+                .project
+                .or_else(|| alias.project.as_ref().map(|p| p.to_string())),
+            task: self
+                .task
+                .or_else(|| alias.task.as_ref().map(|t| t.to_string())),
+            billable: self.billable.or(alias.billable),
+            notes: self.notes.or_else(|| alias.notes.clone()),
+            hours: match self.hours {
+                Some(hours) => Some(hours),
+                None => alias.duration.as_deref().map(parse_hours).transpose()?,
+            },
+        })
+    }
+}
+
+/// starts a timer for the target's project, or the one the current directory
+/// belongs to, returning the new entry and the credentials for its account
+pub async fn start(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    target: EntryTarget,
+    ago: Option<f32>,
+) -> anyhow::Result<(TimeEntry, Arc<dyn TimeTracker>)> {
+    let (project, account) = target_project(target.project.as_deref(), account)?;
+    let tracker = auth::credentials(config_dir, settings, account.as_deref())
+        .await?
+        .tracker();
+
+    let assignments = tracker.project_assignments().await?;
+    let assignment = find_project_assignment(&assignments, &project)?;
+    let task = pick_task(
+        config_dir,
+        settings,
+        assignment,
+        target.task.as_deref(),
+        target.billable,
+    )?;
+
+    // the entry is for today, so it can't have started yesterday
+    if let Some(ago) = ago {
+        let now = Local::now();
+        if settings.date(now - Duration::minutes((ago * 60.0).round() as i64)) != settings.today() {
+            return Err(anyhow!(
+                "--ago can't go back past midnight, use `log` instead"
+            ));
+        }
+    }
+
+    let entry = tracker
+        .start_timer(
+            assignment.project_id,
+            task.task_id,
+            settings.today(),
+            target.notes,
+        )
+        .await?;
+    remember_task(config_dir, assignment, &task)?;
+
+    if let Some(ago) = ago {
+        backdate_running_timer(tracker.as_ref(), settings, &entry, ago).await?;
+        println!(
+            "Started timer for {} ({}) {} ago",
+            assignment.project_name,
+            task.task_name,
+            decimal_hours_to_string(ago).trim()
+        );
+    } else {
+        println!(
+            "Started timer for {} ({})",
+            assignment.project_name, task.task_name
+        );
+    }
+
+    Ok((entry, tracker))
+}
+
+/// makes a timer that was just started look like it started `ago` hours
+/// earlier, by moving its start time or by giving it hours to count up from,
+/// depending on how the account tracks time
+async fn backdate_running_timer(
+    tracker: &dyn TimeTracker,
+    settings: &Settings,
+    entry: &TimeEntry,
+    ago: f32,
+) -> anyhow::Result<()> {
+    let request = if tracker.wants_timestamp_timers().await? {
+        let started_at = Local::now() - Duration::minutes((ago * 60.0).round() as i64);
+        EntryUpdate {
+            started_time: Some(harvest_time(settings.time(started_at))),
+            ..Default::default()
+        }
+    } else {
+        EntryUpdate {
+            hours: Some(ago),
+            ..Default::default()
+        }
+    };
+
+    tracker.update_time_entry(entry, request).await?;
+    Ok(())
+}
+
+/// a time of day the way harvest writes them, like `2:30pm`
+fn harvest_time(at: NaiveTime) -> String {
+    at.format("%-I:%M%P").to_string()
+}
+
+/// the task named by `--task` or the project config's `task`, the project's
+/// only task, or one picked from a fuzzy list that starts on the task last
+/// used for the project
+///
+/// harvest decides whether an entry is billable from its task, so `billable`
+/// (or the project config's) narrows the tasks to pick from
+pub fn pick_task(
+    config_dir: &Path,
+    settings: &Settings,
+    assignment: &ProjectAssignment,
+    task: Option<&str>,
+    billable: Option<bool>,
+) -> anyhow::Result<TaskAssignment> {
+    let defaults = find_project(settings, assignment.project_id);
+    let task = task.or_else(|| defaults.as_ref()?.task.as_deref());
+    let billable = billable.or_else(|| defaults.as_ref()?.billable);
+
+    let mut assignment = assignment.clone();
+    if let Some(billable) = billable {
+        assignment.tasks.retain(|t| t.billable == billable);
+    }
+    let kind = match billable {
+        Some(true) => "billable ",
+        Some(false) => "non-billable ",
+        None => "",
+    };
+
+    match (task, assignment.tasks.as_slice()) {
+        (Some(task), _) => assignment.find_task(task).cloned().map_err(|_| {
+            anyhow!(
+                "{} has no {}task {:?}, its {}tasks are: {}",
+                assignment.project_name,
+                kind,
+                task,
+                kind,
+                assignment.task_names()
+            )
+        }),
+        (None, []) => Err(anyhow!(
+            "{} has no active {}tasks",
+            assignment.project_name,
+            kind
+        )),
+        (None, [only_task]) => Ok(only_task.clone()),
+        (None, tasks) if prompt::is_interactive() => {
+            let last_task = State::load(config_dir)?
+                .last_tasks
+                .get(&assignment.project_id)
+                .copied();
+            let default = tasks
+                .iter()
+                .position(|t| Some(t.task_id) == last_task)
+                .unwrap_or(0);
+            let names: Vec<String> = tasks.iter().map(|t| t.task_name.clone()).collect();
+
+            let index = prompt::fuzzy_select(
+                &format!("Task for {}", assignment.project_name),
+                &names,
+                default,
+            )?;
+            Ok(tasks[index].clone())
+        }
+        (None, _) => Err(anyhow!(
+            "pick a {}task for {} with --task, one of: {}",
+            kind,
+            assignment.project_name,
+            assignment.task_names()
+        )),
+    }
+}
+
+/// starts a timer for a watched project without asking anything, with the
+/// project config's task, the project's only task or the one last used for it
+pub async fn start_unattended(
+    config_dir: &Path,
+    settings: &Settings,
+    tracker: &dyn TimeTracker,
+    project: &Project,
+    notes: Option<String>,
+) -> anyhow::Result<TaskAssignment> {
+    let assignment = tracker
+        .project_assignment(project.harvest_project_id)
+        .await?;
+    let last_task = State::load(config_dir)?
+        .last_tasks
+        .get(&assignment.project_id)
+        .copied();
+
+    let tasks: Vec<&TaskAssignment> = assignment
+        .tasks
+        .iter()
+        .filter(|t| {
+            project
+                .billable
+                .is_none_or(|billable| t.billable == billable)
+        })
+        .collect();
+    let task = match (&project.task, tasks.as_slice()) {
+        (Some(task), _) => assignment.find_task(task)?,
+        (None, [only_task]) => *only_task,
+        (None, tasks) => tasks
+            .iter()
+            .copied()
+            .find(|t| Some(t.task_id) == last_task)
+            .ok_or_else(|| {
+                anyhow!(
+                    "set `task` in {} to start timers for it from notifications",
+                    project.config_path.display()
+                )
+            })?,
+    };
+
+    tracker
+        .start_timer(assignment.project_id, task.task_id, settings.today(), notes)
+        .await?;
+    remember_task(config_dir, &assignment, task)?;
+
+    Ok(task.clone())
+}
+
+/// makes `task` the default the next time a task is picked for the project
+fn remember_task(
+    config_dir: &Path,
+    assignment: &ProjectAssignment,
+    task: &TaskAssignment,
+) -> anyhow::Result<()> {
+    let mut state = State::load(config_dir)?;
+    state.last_tasks.insert(assignment.project_id, task.task_id);
+    state.save(config_dir)
+}
+
+/// the project named, or the one the current directory belongs to along with
+/// the account it's billed to
+fn target_project(
+    project: Option<&str>,
+    account: Option<&str>,
+) -> anyhow::Result<(String, Option<String>)> {
+    match project {
+        Some(project) => Ok((project.to_string(), account.map(str::to_string))),
+        None => {
+            let project = current_project()?;
+            Ok((
+                project.harvest_project_id.to_string(),
+                project.account.or(account.map(str::to_string)),
+            ))
+        }
+    }
+}
+
+/// stops the running timer and starts one for another project, either the
+/// one named or the one the current directory belongs to
+pub async fn switch(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    project: Option<&str>,
+    task: Option<&str>,
+    keep_notes: bool,
+) -> anyhow::Result<()> {
+    let (project, account) = target_project(project, account)?;
+    let tracker = auth::credentials(config_dir, settings, account.as_deref())
+        .await?
+        .tracker();
+
+    let assignments = tracker.project_assignments().await?;
+    let assignment = find_project_assignment(&assignments, &project)?;
+    let task = pick_task(config_dir, settings, assignment, task, None)?;
+
+    let notes = match tracker.running_time_entry().await? {
+        Some(running) => {
+            tracker.stop_time_entry(&running).await?;
+            println!(
+                "Stopped timer for {} ({})",
+                running.project.name, running.task.name
+            );
+            running.notes.filter(|_| keep_notes)
+        }
+        None => None,
+    };
+
+    tracker
+        .start_timer(assignment.project_id, task.task_id, settings.today(), notes)
+        .await?;
+    remember_task(config_dir, assignment, &task)?;
+
+    println!(
+        "Started timer for {} ({})",
+        assignment.project_name, task.task_name
+    );
+
+    Ok(())
+}
+
+/// stops the running timer, rounding its hours when the project's config asks
+/// for it
+pub async fn stop(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> anyhow::Result<()> {
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+
+    let running = tracker
+        .running_time_entry()
+        .await?
+        .ok_or_else(|| anyhow!("no timer is running"))?;
+
+    println!(
+        "{}",
+        stop_entry(tracker.as_ref(), settings, &running, None).await?
+    );
+
+    Ok(())
+}
+
+/// a timer `stop_entry` stopped
+pub struct StoppedTimer {
+    pub entry: TimeEntry,
+
+    /// hours the timer ran for
+    pub hours: f32,
+
+    /// hours the entry was left with, after backdating and rounding
+    pub kept: f32,
+}
+
+impl fmt::Display for StoppedTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Stopped timer for {} ({}), {}",
+            self.entry.project.name,
+            self.entry.task.name,
+            decimal_hours_to_string(self.hours).trim()
+        )?;
+        if self.kept != self.hours {
+            write!(f, " kept as {}", decimal_hours_to_string(self.kept).trim())?;
+        }
+        Ok(())
+    }
+}
+
+/// stops a running entry, as of `ended_at` when that's earlier, rounding its
+/// hours when the project's config asks for it
+pub async fn stop_entry(
+    tracker: &dyn TimeTracker,
+    settings: &Settings,
+    running: &TimeEntry,
+    ended_at: Option<DateTime<Local>>,
+) -> anyhow::Result<StoppedTimer> {
+    let stopped = tracker.stop_time_entry(running).await?;
+    let hours = stopped.hours;
+
+    let unused = ended_at
+        .map(|ended_at| (Local::now() - ended_at).num_minutes().max(0) as f32 / 60.0)
+        .unwrap_or_default();
+    let rounding = find_project(settings, running.project.id).and_then(|project| project.rounding);
+    let backdated = (hours - unused).max(0.0);
+    let kept = rounding.map(|r| r.apply(backdated)).unwrap_or(backdated);
+
+    // harvest's hours are rounded to the minute already, so anything under half
+    // a minute apart is the same
+    if (kept - hours).abs() < 1.0 / 120.0 {
+        return Ok(StoppedTimer {
+            entry: stopped,
+            hours,
+            kept: hours,
+        });
+    }
+
+    // timestamp timers work their hours out from the start and end times,
+    // so it's the end that moves
+    let started = stopped.started_time.as_deref().map(parse_time);
+    let request = match started {
+        Some(Ok(started)) if tracker.wants_timestamp_timers().await? => EntryUpdate {
+            ended_time: Some(harvest_time(
+                started + Duration::minutes((kept * 60.0).round() as i64),
+            )),
+            ..Default::default()
+        },
+        _ => EntryUpdate {
+            hours: Some(kept),
+            ..Default::default()
+        },
+    };
+    tracker.update_time_entry(&stopped, request).await?;
+
+    Ok(StoppedTimer {
+        entry: stopped,
+        hours,
+        kept,
+    })
+}
+
+/// restarts the most recently updated of today's stopped time entries
+pub async fn resume(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> anyhow::Result<()> {
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+
+    let today = settings.today();
+    let entries = tracker.time_entries(today, today).await?;
+
+    if entries.iter().any(|e| e.is_running) {
+        return Err(anyhow!("a timer is already running"));
+    }
+
+    let entry = entries
+        .iter()
+        .max_by_key(|e| e.updated_at)
+        .ok_or_else(|| anyhow!("there are no time entries today to resume"))?;
+
+    tracker.restart_time_entry(entry).await?;
+
+    println!(
+        "Resumed timer for {} ({})",
+        entry.project.name, entry.task.name
+    );
+
+    Ok(())
+}
+
+/// prints the running timer and how much time has been tracked today
+pub async fn status(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+) -> anyhow::Result<()> {
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+
+    let today = settings.today();
+    let entries = tracker.time_entries(today, today).await?;
+
+    match entries.iter().find(|e| e.is_running) {
+        Some(running) => println!(
+            "Timer running for {} ({}), {}",
+            running.project.name,
+            running.task.name,
+            decimal_hours_to_string(running.current_hours()).trim()
+        ),
+        None => println!("No timer running"),
+    }
+
+    let total: f32 = entries.iter().map(|e| e.current_hours()).sum();
+    println!("Today: {}", decimal_hours_to_string(total).trim());
+
+    // activity `watch` has seen with no timer running, when it's running
+    if let Ok(watch) = control::send(config_dir, control::Request::Status) {
+        for project in watch.projects.iter().filter(|p| p.untracked_minutes() > 0) {
+            println!(
+                "Active {} min on {} without a timer",
+                project.untracked_minutes(),
+                project.name
+            );
+        }
+    }
+
+    let progress = targets::daily_progress(settings, &known_projects(settings), &entries, "Today");
+    if !progress.is_empty() {
+        let mut tw = tabwriter::TabWriter::new(io::stdout());
+        for progress in progress {
+            writeln!(&mut tw, "{}", progress)?;
+        }
+        tw.flush()?;
+    }
+
+    Ok(())
+}
+
+/// appends a line to the running timer's notes
+pub async fn note(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    text: &str,
+) -> anyhow::Result<()> {
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+
+    let running = tracker
+        .running_time_entry()
+        .await?
+        .ok_or_else(|| anyhow!("no timer is running"))?;
+
+    let notes = match running.notes.as_deref().map(str::trim) {
+        Some(existing) if !existing.is_empty() => format!("{}\n{}", existing, text),
+        _ => text.to_string(),
+    };
+
+    tracker
+        .update_time_entry(
+            &running,
+            EntryUpdate {
+                notes: Some(notes),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    println!(
+        "Added note to {} ({})",
+        running.project.name, running.task.name
+    );
+
+    Ok(())
+}
+
+/// parses a duration like `1.5h`, `90m`, `1h30m`, `1:30` or `1.5` into
+/// decimal hours
+pub fn parse_hours(s: &str) -> anyhow::Result<f32> {
+    let s = s.trim().to_lowercase();
+    let invalid = || anyhow!("{:?} isn't a duration like 1.5h, 90m, 1h30m or 1:30", s);
+    // minutes after hours, as in 1:30 or 1h30m, are less than an hour
+    let minutes_past = |minutes: &str| {
+        minutes
+            .parse::<u32>()
+            .ok()
+            .filter(|minutes| *minutes < 60)
+            .map(|minutes| minutes as f32 / 60.0)
+            .ok_or_else(invalid)
+    };
+
+    let hours = if let Some((hours, minutes)) = s.split_once(':') {
+        hours.parse::<u32>().map_err(|_| invalid())? as f32 + minutes_past(minutes)?
+    } else if let Some((hours, minutes)) = s.split_once('h') {
+        hours.parse::<f32>().map_err(|_| invalid())?
+            + match minutes.trim_end_matches('m') {
+                "" => 0.0,
+                minutes => minutes_past(minutes)?,
+            }
+    } else if let Some(minutes) = s.strip_suffix('m') {
+        minutes.parse::<u32>().map_err(|_| invalid())? as f32 / 60.0
+    } else {
+        s.parse::<f32>().map_err(|_| invalid())?
+    };
+
+    if !(hours > 0.0 && hours <= 24.0) {
+        return Err(anyhow!(
+            "{:?} should be more than nothing and at most 24h",
+            s
+        ));
+    }
+
+    Ok(hours)
+}
+
+/// creates a stopped time entry for time already spent, on the target's
+/// project or the one the current directory belongs to
+pub async fn log(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    target: EntryTarget,
+    date: Option<NaiveDate>,
+) -> anyhow::Result<()> {
+    let hours = target
+        .hours
+        .ok_or_else(|| anyhow!("the alias has no duration, pass how long after it"))?;
+    let (project, account) = target_project(target.project.as_deref(), account)?;
+    let tracker = auth::credentials(config_dir, settings, account.as_deref())
+        .await?
+        .tracker();
+
+    let date = date.unwrap_or_else(|| settings.today());
+
+    // harvest locks entries in approved or closed periods, so a locked entry
+    // means the whole day is
+    if let Some(locked) = tracker
+        .time_entries(date, date)
+        .await?
+        .into_iter()
+        .find(|e| e.is_locked)
+    {
+        return Err(anyhow!(
+            "{} is locked{}, time can't be logged for it",
+            date,
+            locked
+                .locked_reason
+                .map(|reason| format!(" ({})", reason))
+                .unwrap_or_default()
+        ));
+    }
+
+    let assignments = tracker.project_assignments().await?;
+    let assignment = find_project_assignment(&assignments, &project)?;
+    let task = pick_task(
+        config_dir,
+        settings,
+        assignment,
+        target.task.as_deref(),
+        target.billable,
+    )?;
+
+    log_task(
+        config_dir,
+        tracker.as_ref(),
+        assignment,
+        &task,
+        date,
+        hours,
+        target.notes,
+    )
+    .await
+}
+
+/// logs time for a task already picked, remembering it for the project
+pub async fn log_task(
+    config_dir: &Path,
+    tracker: &dyn TimeTracker,
+    assignment: &ProjectAssignment,
+    task: &TaskAssignment,
+    date: NaiveDate,
+    hours: f32,
+    notes: Option<String>,
+) -> anyhow::Result<()> {
+    tracker
+        .log_time(assignment.project_id, task.task_id, date, hours, notes)
+        .await?;
+    remember_task(config_dir, assignment, task)?;
+
+    println!(
+        "Logged {} on {} for {} ({})",
+        decimal_hours_to_string(hours).trim(),
+        date,
+        assignment.project_name,
+        task.task_name
+    );
+
+    Ok(())
+}
+
+/// today's time entries, oldest first so their indexes stay put as more are
+/// added
+async fn todays_entries(
+    tracker: &dyn TimeTracker,
+    settings: &Settings,
+) -> anyhow::Result<Vec<TimeEntry>> {
+    let today = settings.today();
+    let mut entries = tracker.time_entries(today, today).await?;
+    entries.sort_by_key(|a| a.created_at);
+    Ok(entries)
+}
+
+/// prints entries with the 1-based indexes `select_entry` accepts
+fn print_entries(entries: &[TimeEntry]) -> anyhow::Result<()> {
+    let mut tw = tabwriter::TabWriter::new(io::stdout());
+
+    for (index, entry) in entries.iter().enumerate() {
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            index + 1,
+            strip_newlines_and_tabs(&entry.project.name),
+            strip_newlines_and_tabs(&entry.task.name),
+            decimal_hours_to_string(entry.hours),
+            if entry.is_running { "running" } else { "" },
+            truncate_with_ellipsis(entry.notes.as_deref().unwrap_or("(none)"), 60)
+        )?;
+    }
+
+    tw.flush()?;
+    Ok(())
+}
+
+/// finds an entry by its index in `print_entries`, or by its harvest id
+fn select_entry<'a>(entries: &'a [TimeEntry], entry: &str) -> anyhow::Result<&'a TimeEntry> {
+    entry
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| entries.get(index.checked_sub(1)?))
+        .or_else(|| entries.iter().find(|e| e.id.to_string() == entry))
+        .ok_or_else(|| anyhow!("there's no entry {} today", entry))
+}
+
+/// the entry given on the command line, or one picked after listing today's
+/// entries
+fn choose_entry<'a>(
+    entries: &'a [TimeEntry],
+    entry: Option<&str>,
+) -> anyhow::Result<&'a TimeEntry> {
+    if entries.is_empty() {
+        return Err(anyhow!("there are no time entries today"));
+    }
+
+    match entry {
+        Some(entry) => select_entry(entries, entry),
+        None if prompt::is_interactive() => {
+            print_entries(entries)?;
+            select_entry(entries, &prompt::prompt("Entry: ")?)
+        }
+        None => {
+            print_entries(entries)?;
+            Err(anyhow!("pass the index of the entry"))
+        }
+    }
+}
+
+/// changes to make to a time entry, fields left as None stay the same
+#[derive(Debug, Default)]
+pub struct EntryChanges {
+    pub hours: Option<f32>,
+    pub notes: Option<String>,
+    pub project: Option<String>,
+    pub task: Option<String>,
+}
+
+impl EntryChanges {
+    fn is_empty(&self) -> bool {
+        self.hours.is_none()
+            && self.notes.is_none()
+            && self.project.is_none()
+            && self.task.is_none()
+    }
+
+    /// asks for each field, keeping the current value when the answer is blank
+    fn prompt(entry: &TimeEntry) -> anyhow::Result<EntryChanges> {
+        let answer = |label: &str, current: String| -> anyhow::Result<Option<String>> {
+            let answer = prompt::prompt(&format!("{} [{}]: ", label, current))?;
+            Ok(Some(answer).filter(|a| !a.is_empty()))
+        };
+
+        Ok(EntryChanges {
+            hours: answer(
+                "Hours",
+                decimal_hours_to_string(entry.hours).trim().to_string(),
+            )?
+            .map(|hours| parse_hours(&hours))
+            .transpose()?,
+            notes: answer("Notes", entry.notes.clone().unwrap_or_default())?,
+            project: answer("Project", entry.project.name.clone())?,
+            task: answer("Task", entry.task.name.clone())?,
+        })
+    }
+}
+
+/// changes the hours, notes, task or project of one of today's entries,
+/// prompting for the entry and the changes when they aren't passed
+pub async fn edit(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    entry: Option<&str>,
+    changes: EntryChanges,
+) -> anyhow::Result<()> {
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+
+    let entries = todays_entries(tracker.as_ref(), settings).await?;
+    let entry = choose_entry(&entries, entry)?;
+
+    let changes = if changes.is_empty() && prompt::is_interactive() {
+        EntryChanges::prompt(entry)?
+    } else {
+        changes
+    };
+    if changes.is_empty() {
+        return Err(anyhow!(
+            "nothing to change, pass --hours, --notes, --project or --task"
+        ));
+    }
+
+    let mut request = EntryUpdate {
+        hours: changes.hours,
+        notes: changes.notes,
+        ..Default::default()
+    };
+
+    if changes.project.is_some() || changes.task.is_some() {
+        let assignments = tracker.project_assignments().await?;
+        let project = changes
+            .project
+            .clone()
+            .unwrap_or_else(|| entry.project.id.to_string());
+        let assignment = find_project_assignment(&assignments, &project)?;
+        // keep the task when it's also on the new project
+        let task = changes
+            .task
+            .or_else(|| Some(entry.task.id.to_string()))
+            .filter(|task| assignment.find_task(task).is_ok());
+        let task = pick_task(config_dir, settings, assignment, task.as_deref(), None)?;
+
+        request.project_id = Some(assignment.project_id);
+        request.task_id = Some(task.task_id);
+    }
+
+    let updated = tracker.update_time_entry(entry, request).await?;
+
+    println!(
+        "Updated entry to {} ({}), {}",
+        updated.project.name,
+        updated.task.name,
+        decimal_hours_to_string(updated.hours).trim()
+    );
+
+    Ok(())
+}
+
+/// deletes one of today's entries by index, or any of the user's entries by
+/// harvest id, after confirming
+pub async fn delete(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    entry: &str,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+
+    let entries = todays_entries(tracker.as_ref(), settings).await?;
+    let entry = match select_entry(&entries, entry) {
+        Ok(entry) => entry.clone(),
+        Err(_) => {
+            let id = entry
+                .parse()
+                .map_err(|_| anyhow!("there's no entry {} today", entry))?;
+            tracker.time_entry(id).await?
+        }
+    };
+
+    let description = format!(
+        "{} for {} ({}) on {}",
+        decimal_hours_to_string(entry.hours).trim(),
+        entry.project.name,
+        entry.task.name,
+        entry.spent_date
+    );
+
+    if !yes {
+        if !prompt::is_interactive() {
+            return Err(anyhow!("pass --yes to delete without confirming"));
+        }
+        if !prompt::confirm(&format!("Delete {}?", description))? {
+            return Ok(());
+        }
+    }
+
+    tracker.delete_time_entry(&entry).await?;
+
+    println!("Deleted {}", description);
+
+    Ok(())
+}
+
+/// parses a time of day like `14:30`, `2:30pm` or `2pm`
+pub fn parse_time(s: &str) -> anyhow::Result<NaiveTime> {
+    let s = s.trim().to_lowercase();
+    // chrono won't parse a time without minutes, so 2pm is read as 2:00pm
+    let with_minutes = match s.strip_suffix("am").or_else(|| s.strip_suffix("pm")) {
+        Some(hour) if !hour.contains(':') => format!("{}:00{}", hour, &s[hour.len()..]),
+        _ => s.clone(),
+    };
+    ["%H:%M", "%I:%M%p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&with_minutes, format).ok())
+        .ok_or_else(|| anyhow!("{:?} isn't a time like 14:30 or 2:30pm", s))
+}
+
+/// ends the running timer at a time earlier today and starts one for the
+/// target's project from then, for when switching projects was forgotten
+pub async fn split(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    at: NaiveTime,
+    target: EntryTarget,
+) -> anyhow::Result<()> {
+    let tracker = auth::credentials(config_dir, settings, account)
+        .await?
+        .tracker();
+    let running = tracker
+        .running_time_entry()
+        .await?
+        .ok_or_else(|| anyhow!("no timer is running"))?;
+
+    let since = settings.time(Local::now()) - at;
+    let hours = running.current_hours();
+    let moved = since.num_minutes() as f32 / 60.0;
+    if since < Duration::zero() {
+        return Err(anyhow!("{} hasn't happened yet", at.format("%H:%M")));
+    }
+    if moved > hours {
+        return Err(anyhow!(
+            "the running timer only has {}, which started after {}",
+            decimal_hours_to_string(hours).trim(),
+            at.format("%H:%M")
+        ));
+    }
+
+    // the running entry only loses its time once the new one has it, and
+    // carries on if the new one can't be started
+    let stopped = tracker.stop_time_entry(&running).await?;
+    if let Err(e) = start(config_dir, settings, account, target, Some(moved)).await {
+        tracker.restart_time_entry(&stopped).await?;
+        return Err(e);
+    }
+
+    // the running entry keeps the time up to `at`, unrounded so the two
+    // entries add up to what was tracked
+    let kept = (stopped.hours - moved).max(0.0);
+    let request = if stopped.started_time.is_some() && tracker.wants_timestamp_timers().await? {
+        EntryUpdate {
+            ended_time: Some(harvest_time(at)),
+            ..Default::default()
+        }
+    } else {
+        EntryUpdate {
+            hours: Some(kept),
+            ..Default::default()
+        }
+    };
+    tracker.update_time_entry(&stopped, request).await?;
+    println!(
+        "Ended {} ({}) at {}, {}",
+        stopped.project.name,
+        stopped.task.name,
+        at.format("%H:%M"),
+        decimal_hours_to_string(kept).trim()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hours_in_each_form() {
+        assert_eq!(parse_hours("1.5h").unwrap(), 1.5);
+        assert_eq!(parse_hours("90m").unwrap(), 1.5);
+        assert_eq!(parse_hours("1h30m").unwrap(), 1.5);
+        assert_eq!(parse_hours("1h30").unwrap(), 1.5);
+        assert_eq!(parse_hours("2h").unwrap(), 2.0);
+        assert_eq!(parse_hours("1:30").unwrap(), 1.5);
+        assert_eq!(parse_hours(" 1.5 ").unwrap(), 1.5);
+        assert_eq!(parse_hours("1H30M").unwrap(), 1.5);
+    }
+
+    #[test]
+    fn rejects_minutes_past_an_hour_after_hours() {
+        assert!(parse_hours("0:90").is_err());
+        assert!(parse_hours("1:60").is_err());
+        assert!(parse_hours("1h60m").is_err());
+        assert_eq!(parse_hours("0:59").unwrap(), 59.0 / 60.0);
+    }
+
+    #[test]
+    fn writes_times_the_way_parse_time_reads_them() {
+        for (time, written) in [("9:05", "9:05am"), ("14:30", "2:30pm"), ("0:00", "12:00am")] {
+            let time = parse_time(time).unwrap();
+            assert_eq!(harvest_time(time), written);
+            assert_eq!(parse_time(written).unwrap(), time);
+        }
+    }
+
+    #[test]
+    fn rejects_nothing_too_much_and_nonsense() {
+        assert!(parse_hours("0").is_err());
+        assert!(parse_hours("0:00").is_err());
+        assert!(parse_hours("25h").is_err());
+        assert!(parse_hours("-1").is_err());
+        assert!(parse_hours("an hour").is_err());
+        assert!(parse_hours("1:x").is_err());
+        assert_eq!(parse_hours("24h").unwrap(), 24.0);
+    }
+
+    #[test]
+    fn parses_times_of_day() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(parse_time("14:30").unwrap(), time(14, 30));
+        assert_eq!(parse_time("09:05").unwrap(), time(9, 5));
+        assert_eq!(parse_time("2:30pm").unwrap(), time(14, 30));
+        assert_eq!(parse_time("12:00am").unwrap(), time(0, 0));
+        assert_eq!(parse_time("9am").unwrap(), time(9, 0));
+        assert!(parse_time("25:00").is_err());
+        assert!(parse_time("noon").is_err());
+    }
+}
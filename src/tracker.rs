@@ -0,0 +1,262 @@
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// something an entry refers to, like its project
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Named {
+    pub id: i32,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// a time entry, as much of it as clockhand reads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub spent_date: NaiveDate,
+    #[serde(default)]
+    pub hours: f32,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub is_running: bool,
+    pub timer_started_at: Option<DateTime<Utc>>,
+
+    /// times of day like `8:00am`, for accounts that track them
+    pub started_time: Option<String>,
+    pub ended_time: Option<String>,
+
+    pub project: Named,
+    pub task: Named,
+    pub user: Named,
+    pub client: Option<Named>,
+    #[serde(default)]
+    pub billable: bool,
+    pub billable_rate: Option<f32>,
+    pub approval_status: Option<String>,
+    #[serde(default)]
+    pub is_locked: bool,
+    pub locked_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TimeEntry {
+    /// hours including the time since the timer was started when it's still
+    /// running, as `hours` only counts up to when it was last saved
+    pub fn current_hours(&self) -> f32 {
+        let elapsed = match (self.is_running, self.timer_started_at) {
+            (true, Some(started)) => (Utc::now() - started).num_seconds().max(0) as f32 / 3600.0,
+            _ => 0.0,
+        };
+        self.hours + elapsed
+    }
+
+    /// where the entry stands in harvest's timesheet approval: "approved",
+    /// "submitted", "locked" for one in a closed period, or "" while it can
+    /// still be edited
+    pub fn status(&self) -> &'static str {
+        match self.approval_status.as_deref() {
+            Some("approved") => "approved",
+            Some("submitted") => "submitted",
+            _ if self.is_locked => "locked",
+            _ => "",
+        }
+    }
+}
+
+/// changes to a time entry, fields left as None stay the same
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EntryUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hours: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_time: Option<String>,
+}
+
+/// the person credentials belong to
+#[derive(Debug, Clone)]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+}
+
+/// a project the current user can track time against
+#[derive(Debug, Clone)]
+pub struct ProjectAssignment {
+    pub project_id: i32,
+    pub project_name: String,
+    pub client_name: Option<String>,
+    pub tasks: Vec<TaskAssignment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskAssignment {
+    pub task_id: i32,
+    pub task_name: String,
+    pub billable: bool,
+}
+
+impl ProjectAssignment {
+    /// finds a task by id or case-insensitive name
+    pub fn find_task(&self, task: &str) -> anyhow::Result<&TaskAssignment> {
+        self.tasks
+            .iter()
+            .find(|t| t.task_id.to_string() == task || t.task_name.eq_ignore_ascii_case(task))
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} has no task {:?}, its tasks are: {}",
+                    self.project_name,
+                    task,
+                    self.task_names()
+                )
+            })
+    }
+
+    pub fn task_names(&self) -> String {
+        self.tasks
+            .iter()
+            .map(|t| t.task_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// finds an assignment by project id or case-insensitive project name
+pub fn find_project_assignment<'a>(
+    assignments: &'a [ProjectAssignment],
+    project: &str,
+) -> anyhow::Result<&'a ProjectAssignment> {
+    assignments
+        .iter()
+        .find(|a| {
+            a.project_id.to_string() == project || a.project_name.eq_ignore_ascii_case(project)
+        })
+        .ok_or_else(|| anyhow!("you aren't assigned to a harvest project {:?}", project))
+}
+
+/// a drafted invoice
+#[derive(Debug, Clone)]
+pub struct Invoice {
+    pub number: String,
+
+    /// where it can be seen in the tracker's web app
+    pub url: String,
+}
+
+/// what clockhand needs from a time tracking service, so commands don't
+/// depend on which one it is. harvest, in api.rs, is the only one so far
+#[async_trait]
+pub trait TimeTracker: Send + Sync {
+    /// the person the credentials belong to
+    async fn current_user(&self) -> anyhow::Result<User>;
+
+    /// the id of the user the credentials belong to
+    async fn current_user_id(&self) -> anyhow::Result<i32>;
+
+    /// everyone in the account, which may need an administrator's credentials
+    async fn user_ids(&self) -> anyhow::Result<Vec<i32>>;
+
+    /// the current user's running timer, if there is one
+    async fn running_time_entry(&self) -> anyhow::Result<Option<TimeEntry>>;
+
+    /// one of the current user's entries by id
+    async fn time_entry(&self, id: i64) -> anyhow::Result<TimeEntry>;
+
+    /// the current user's entries spent between two dates, inclusive
+    async fn time_entries(&self, from: NaiveDate, to: NaiveDate) -> anyhow::Result<Vec<TimeEntry>>;
+
+    /// a user's entries spent between two dates, inclusive
+    async fn user_time_entries(
+        &self,
+        user_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<TimeEntry>>;
+
+    /// every project the current user is assigned to, with its tasks
+    async fn project_assignments(&self) -> anyhow::Result<Vec<ProjectAssignment>>;
+
+    /// the current user's assignment to a project, with its tasks
+    async fn project_assignment(&self, project_id: i32) -> anyhow::Result<ProjectAssignment> {
+        self.project_assignments()
+            .await?
+            .into_iter()
+            .find(|a| a.project_id == project_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "harvest project {} doesn't exist or you aren't assigned to it",
+                    project_id
+                )
+            })
+    }
+
+    /// whether the account tracks time with start and end times rather than
+    /// durations
+    async fn wants_timestamp_timers(&self) -> anyhow::Result<bool>;
+
+    /// starts a timer for today, stopping any other that's running
+    async fn start_timer(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        spent_date: NaiveDate,
+        notes: Option<String>,
+    ) -> anyhow::Result<TimeEntry>;
+
+    /// stops a running timer
+    async fn stop_time_entry(&self, entry: &TimeEntry) -> anyhow::Result<TimeEntry>;
+
+    /// starts a stopped entry's timer again
+    async fn restart_time_entry(&self, entry: &TimeEntry) -> anyhow::Result<TimeEntry>;
+
+    /// records time already spent on a day, without a running timer
+    async fn log_time(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        spent_date: NaiveDate,
+        hours: f32,
+        notes: Option<String>,
+    ) -> anyhow::Result<TimeEntry>;
+
+    /// changes the fields of an entry that are set in `update`
+    async fn update_time_entry(
+        &self,
+        entry: &TimeEntry,
+        update: EntryUpdate,
+    ) -> anyhow::Result<TimeEntry>;
+
+    async fn delete_time_entry(&self, entry: &TimeEntry) -> anyhow::Result<()>;
+
+    /// every active client in the account
+    async fn clients(&self) -> anyhow::Result<Vec<Named>>;
+
+    /// billable entries for a client's projects that haven't been invoiced, by
+    /// anyone, spent up to and including `through`
+    async fn uninvoiced_time_entries(
+        &self,
+        client_id: i32,
+        through: NaiveDate,
+    ) -> anyhow::Result<Vec<TimeEntry>>;
+
+    /// drafts an invoice for the projects' uninvoiced billable time up to
+    /// `through`, summarized by task
+    async fn draft_invoice(
+        &self,
+        client_id: i32,
+        project_ids: Vec<i32>,
+        through: NaiveDate,
+        issue_date: NaiveDate,
+    ) -> anyhow::Result<Invoice>;
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    auth::{self, AccessTokenConfig},
+    config::Settings,
+    migrate::{self, ConfigKind},
+    project::{is_toml, ProjectConfig},
+};
+
+enum Severity {
+    Error,
+    Warning,
+}
+
+/// a problem found in a config file, printed like a compiler diagnostic
+struct Diagnostic {
+    path: PathBuf,
+    line: Option<usize>,
+    column: Option<usize>,
+    severity: Severity,
+    message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, ": {}: {}", severity, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn error(&mut self, path: &Path, message: impl Into<String>) {
+        self.push(path, None, Severity::Error, message);
+    }
+
+    fn push(
+        &mut self,
+        path: &Path,
+        line_column: Option<(usize, usize)>,
+        severity: Severity,
+        message: impl Into<String>,
+    ) {
+        self.0.push(Diagnostic {
+            path: path.to_path_buf(),
+            line: line_column.map(|(line, _)| line),
+            column: line_column.map(|(_, column)| column),
+            severity,
+            message: message.into(),
+        });
+    }
+
+    fn error_count(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|d| matches!(d.severity, Severity::Error))
+            .count()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Toml,
+}
+
+/// parses a file while collecting keys that serde ignored, which are most
+/// likely typos
+fn parse_file<T: DeserializeOwned>(
+    diagnostics: &mut Diagnostics,
+    path: &Path,
+    format: Format,
+) -> Option<T> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            diagnostics.error(path, format!("couldn't read the file: {}", e));
+            return None;
+        }
+    };
+
+    let mut unknown_keys = Vec::new();
+    let parsed = match format {
+        Format::Json => {
+            let mut deserializer = serde_json::Deserializer::from_str(&contents);
+            serde_ignored::deserialize(&mut deserializer, |key| unknown_keys.push(key.to_string()))
+                .map_err(|e| (Some((e.line(), e.column())), e.to_string()))
+        }
+        Format::Toml => {
+            let deserializer = toml::Deserializer::new(&contents);
+            serde_ignored::deserialize(deserializer, |key| unknown_keys.push(key.to_string()))
+                .map_err(|e: toml::de::Error| {
+                    let line_column = e.span().map(|span| line_column(&contents, span.start));
+                    (line_column, e.message().to_string())
+                })
+        }
+    };
+
+    for key in unknown_keys {
+        let leaf = key.rsplit('.').next().unwrap_or(&key).to_string();
+        diagnostics.push(
+            path,
+            find_key(&contents, &leaf),
+            Severity::Warning,
+            format!("unknown key `{}`", key),
+        );
+    }
+
+    match parsed {
+        Ok(value) => Some(value),
+        Err((line_column, message)) => {
+            diagnostics.push(path, line_column, Severity::Error, message);
+            None
+        }
+    }
+}
+
+/// converts a byte offset into a 1-based line and column
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+/// finds where a key is written, for pointing at unknown keys
+fn find_key(contents: &str, key: &str) -> Option<(usize, usize)> {
+    contents.lines().enumerate().find_map(|(i, line)| {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let bare = trimmed.trim_start_matches('"');
+        (bare.starts_with(key)
+            && bare[key.len()..]
+                .trim_start_matches('"')
+                .trim_start()
+                .starts_with([':', '=']))
+        .then_some((i + 1, indent + 1))
+    })
+}
+
+/// checks the global settings, credentials and project configs, reporting
+/// every problem found rather than stopping at the first
+pub async fn validate(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    project_config_paths: &[PathBuf],
+) -> anyhow::Result<()> {
+    let mut diagnostics = Diagnostics::default();
+
+    // older config files are upgraded here and by `doctor` rather than
+    // whenever they're read
+    let settings_path = Settings::path(config_dir);
+    if let Err(e) = migrate::migrate_file(&settings_path, ConfigKind::Settings) {
+        diagnostics.error(&settings_path, format!("{:#}", e));
+    }
+    if settings_path.exists() {
+        parse_file::<Settings>(&mut diagnostics, &settings_path, Format::Toml);
+    }
+
+    let mut projects_by_account: HashMap<Option<String>, Vec<(PathBuf, ProjectConfig)>> =
+        HashMap::new();
+    for path in project_config_paths {
+        if let Err(e) = migrate::migrate_file(path, ConfigKind::Project) {
+            diagnostics.error(path, format!("{:#}", e));
+        }
+        let format = if is_toml(path) {
+            Format::Toml
+        } else {
+            Format::Json
+        };
+        if let Some(project_config) = parse_file::<ProjectConfig>(&mut diagnostics, path, format) {
+            let project_account = project_config
+                .account
+                .clone()
+                .or_else(|| account.map(|a| a.to_string()));
+            projects_by_account
+                .entry(project_account)
+                .or_default()
+                .push((path.clone(), project_config));
+        }
+    }
+
+    // the default account is checked even without projects using it
+    projects_by_account
+        .entry(account.map(|a| a.to_string()))
+        .or_default();
+
+    for (project_account, projects) in projects_by_account {
+        let token_file = auth::access_token_file(config_dir, project_account.as_deref());
+        if token_file.exists() {
+            parse_file::<AccessTokenConfig>(&mut diagnostics, &token_file, Format::Json);
+        }
+
+        let token_data =
+            match auth::validated_credentials(config_dir, settings, project_account.as_deref())
+                .await
+            {
+                Ok(token_data) => token_data,
+                Err(e) => {
+                    diagnostics.error(&token_file, format!("{:#}", e));
+                    continue;
+                }
+            };
+
+        if projects.is_empty() {
+            continue;
+        }
+
+        let assignments = match token_data.tracker().project_assignments().await {
+            Ok(assignments) => assignments,
+            Err(e) => {
+                diagnostics.error(&token_file, format!("{:#}", e));
+                continue;
+            }
+        };
+
+        for (path, project_config) in projects {
+            if !assignments
+                .iter()
+                .any(|a| a.project_id == project_config.harvest_project_id)
+            {
+                diagnostics.push(
+                    &path,
+                    find_key(&std::fs::read_to_string(&path)?, "harvest_project_id"),
+                    Severity::Error,
+                    format!(
+                        "harvest project {} doesn't exist or you aren't assigned to it",
+                        project_config.harvest_project_id
+                    ),
+                );
+            }
+        }
+    }
+
+    for diagnostic in diagnostics.0.iter() {
+        println!("{}", diagnostic);
+    }
+
+    match diagnostics.error_count() {
+        0 => {
+            println!("config is valid");
+            Ok(())
+        }
+        n => Err(anyhow!("found {} error(s)", n)),
+    }
+}
@@ -0,0 +1,1630 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Timelike};
+use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::json;
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver},
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    activity::{self, Activity, Source, TimerState},
+    apps, auth,
+    calendar::{Calendar, Meeting},
+    config::Settings,
+    control::{self, Control},
+    day_summary, decimal_hours_to_string, hooks, idle, logging,
+    notification::{self, Answer, Urgency},
+    project::{
+        canonical_config_path, discover_project_configs, is_project_config_name, project_for_file,
+        project_root, read_project_config, Project, IGNORE_FILE_NAMES,
+    },
+    snooze, start_reminder,
+    state::State,
+    targets, template, timer,
+    tracker::{TimeEntry, TimeTracker},
+};
+
+/// watches project roots and notifies when there's activity without a timer
+/// running for that project
+///
+/// `paths` are project configs, or directories that are searched for project
+/// configs, including ones created while watching
+pub async fn watch(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<String>,
+    paths: &[PathBuf],
+    interval: Duration,
+    track_apps: bool,
+) -> anyhow::Result<()> {
+    let (search_roots, config_paths): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.iter().cloned().partition(|p| p.is_dir());
+
+    // absolute, so they can be matched against paths in events
+    let search_roots: Vec<PathBuf> = search_roots
+        .iter()
+        .map(|root| {
+            dunce::canonicalize(root)
+                .with_context(|| format!("failed to resolve {}", root.display()))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let mut project_config_paths: Vec<PathBuf> = config_paths
+        .iter()
+        .map(|p| canonical_config_path(p))
+        .collect();
+    for root in search_roots.iter() {
+        let discovered = discover_project_configs(root);
+        logging::info!("Found {} project(s) in {:?}", discovered.len(), root);
+        project_config_paths.extend(discovered);
+    }
+
+    // Create a channel to receive file system events
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    // Create a watcher object and register the directories to watch
+    let watcher_tx = tx.clone();
+    let mut watcher: RecommendedWatcher = Watcher::new(
+        move |event: notify::Result<notify::Event>| {
+            let _ = watcher_tx.send(event);
+        },
+        Config::default().with_poll_interval(Duration::from_secs(2)),
+    )
+    .context("couldn't start watching for file changes")?;
+
+    let mut last_request_time = Instant::now().checked_sub(interval).unwrap();
+
+    let mut projects: Vec<Project> = Vec::new();
+
+    for clockhand_config_path in project_config_paths.iter() {
+        projects.push(read_project_config(clockhand_config_path)?);
+    }
+    warn_about_overlapping_roots(&projects);
+
+    let reminders = Reminders::default();
+
+    if track_apps && !cfg!(target_os = "macos") {
+        logging::warn!("--track-apps only works on macOS, ignoring it");
+    }
+
+    // answers `watch status`, `watch pause` and `watch resume`, and passes on
+    // activity from the shell hook
+    let control = Control::default();
+    control.set_projects(&projects);
+    control::listen(config_dir, control.clone(), tx)?;
+
+    let mut project_state: HashMap<PathBuf, ProjectState> = HashMap::new();
+
+    // carries on from where the last watch was stopped, once, so a watch that
+    // crashes later doesn't bring back what's since gone stale
+    let mut state = State::load(config_dir)?;
+    let saved = std::mem::take(&mut state.watch);
+    if let Some(stopped_at) = saved.stopped_at {
+        logging::info!(
+            "carrying on from the watch stopped at {}",
+            stopped_at.format("%Y-%m-%d %H:%M")
+        );
+        state.save(config_dir)?;
+    }
+    control.restore(&saved);
+    for project in projects.iter() {
+        let Some(saved) = saved.projects.get(&project.config_path) else {
+            continue;
+        };
+        reminders.restore(&project.config_path, saved.ignored_reminders);
+        project_state
+            .entry(project.config_path.clone())
+            .or_default()
+            .last_activity = saved.last_event;
+    }
+    let auto_start_gap =
+        chrono::Duration::minutes(settings.watch.auto_start_gap.unwrap_or(30) as i64);
+    let auto_stop_after = settings
+        .watch
+        .auto_stop_after
+        .map(|minutes| chrono::Duration::minutes(minutes as i64));
+    let quiet_after = settings
+        .watch
+        .quiet_after
+        .map(|minutes| chrono::Duration::minutes(minutes as i64));
+    let watching_since = Local::now();
+
+    let away_after = Duration::from_secs(settings.watch.away_after.unwrap_or(5) * 60);
+
+    // daily targets already notified about
+    let mut targets_reached: HashSet<(NaiveDate, Option<i32>)> = HashSet::new();
+
+    // the day the summary was last sent, a watch started after its time
+    // waits until tomorrow
+    let day_summary_at = settings.day_summary_time()?;
+    let mut day_summarised = day_summary_at
+        .filter(|at| settings.time(watching_since) >= *at)
+        .map(|_| settings.date(watching_since));
+
+    // the day it was last checked whether a timer's been started
+    let mut start_checked: Option<NaiveDate> = None;
+
+    let mut watched_roots = HashSet::new();
+    update_watched_roots(
+        &mut watcher,
+        &search_roots,
+        &project_config_paths,
+        &mut watched_roots,
+    );
+
+    // projects can be billed to different accounts, check each one up front
+    let mut validated_accounts = HashSet::new();
+    for project in projects.iter() {
+        let project_account = project.account.clone().or_else(|| account.clone());
+        if validated_accounts.insert(project_account.clone()) {
+            auth::validated_credentials(config_dir, settings, project_account.as_deref()).await?;
+        }
+    }
+
+    // reminders wait until the screen's unlocked
+    let mut screen_lock = ScreenLock::new().await;
+
+    // and until meetings are over, which can be logged afterwards
+    if let Some(name) = &settings.calendar.log_alias {
+        let alias = settings.alias(name)?;
+        if alias.project.is_none() || alias.task.is_none() {
+            return Err(anyhow!(
+                "the {} alias, log_alias in [calendar], needs a project and a task",
+                name
+            ));
+        }
+    }
+    let mut calendar = Calendar::default();
+    calendar.refresh(settings).await;
+
+    // idle timers, quiet timers, ended meetings and the like are checked
+    // every minute
+    let mut checks = time::interval_at(
+        time::Instant::now() + IDLE_CHECK_INTERVAL,
+        IDLE_CHECK_INTERVAL,
+    );
+    checks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut lock_checks = time::interval(LOCK_CHECK_INTERVAL);
+    lock_checks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // the front window is sampled whenever files have been quiet this long
+    let mut app_samples = time::interval_at(
+        time::Instant::now() + APP_SAMPLE_INTERVAL,
+        APP_SAMPLE_INTERVAL,
+    );
+    app_samples.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // the start reminder and day summary go out on time rather than at the
+    // next check
+    let scheduled = time::sleep(until_scheduled(settings, day_summary_at)?);
+    tokio::pin!(scheduled);
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    // Start an event loop to process file system events
+    loop {
+        // each project with activity, how it was noticed and the path that
+        // showed it
+        let changed: Vec<(&Project, Source, Option<PathBuf>)> = tokio::select! {
+            signal = &mut shutdown => {
+                logging::info!("{}, stopping", signal?);
+                return stop(config_dir, settings, &control, &reminders, projects.len());
+            }
+            _ = lock_checks.tick() => {
+                // one catch-up reminder rather than all those held back
+                let Some(held) = screen_lock.check().await else {
+                    continue;
+                };
+                let Some(project) = projects.iter().find(|p| p.config_path == held) else {
+                    continue;
+                };
+                if !control.is_paused()
+                    && settings.is_working_time(Local::now())?
+                    && calendar.current(Local::now()).is_none()
+                {
+                    let tracker =
+                        match project_tracker(config_dir, settings, project, account.as_deref())
+                            .await
+                        {
+                            Ok(tracker) => tracker,
+                            Err(e) => {
+                                logging::warn!("couldn't check the timer for {}: {:#}", project.name, e);
+                                continue;
+                            }
+                        };
+                    let style =
+                        reminder_style(settings, project, reminders.ignored(&project.config_path));
+                    project_state
+                        .entry(project.config_path.clone())
+                        .or_default()
+                        .last_reminded = Some(Instant::now());
+                    last_request_time = Instant::now();
+                    if let Err(e) = notify_project_timer_status(
+                        config_dir, settings, project, &tracker, &style, &reminders, &control,
+                    )
+                    .await
+                    {
+                        logging::warn!("couldn't remind about {}: {:#}", project.name, e);
+                    }
+                }
+                continue;
+            }
+            _ = checks.tick() => {
+                if control.is_paused() {
+                    continue;
+                }
+                calendar.refresh(settings).await;
+                if let Some(auto_stop_after) = auto_stop_after {
+                    if let Err(e) = stop_idle_timers(
+                        config_dir,
+                        settings,
+                        account.as_deref(),
+                        &projects,
+                        &mut project_state,
+                        auto_stop_after,
+                        &control,
+                    )
+                    .await
+                    {
+                        logging::warn!("couldn't check for idle timers: {:#}", e);
+                        continue;
+                    }
+                }
+                // the rest are notifications, which can wait for the screen
+                if !screen_lock.locked {
+                    if let Some(alias) = &settings.calendar.log_alias {
+                        for meeting in calendar.take_ended(watching_since, Local::now()) {
+                            offer_to_log_meeting(
+                                config_dir,
+                                settings,
+                                account.as_deref(),
+                                alias,
+                                meeting,
+                                &control,
+                            );
+                        }
+                    }
+                    if let Some(quiet_after) = quiet_after {
+                        if let Err(e) = remind_quiet_timers(
+                            config_dir,
+                            settings,
+                            account.as_deref(),
+                            &projects,
+                            &mut project_state,
+                            watching_since,
+                            quiet_after,
+                            &control,
+                        )
+                        .await
+                        {
+                            logging::warn!("couldn't check for quiet timers: {:#}", e);
+                            continue;
+                        }
+                    }
+                    // catches up on any held back while locked or paused
+                    if let Err(e) = send_scheduled(
+                        config_dir,
+                        settings,
+                        account.as_deref(),
+                        &projects,
+                        &control,
+                        day_summary_at,
+                        &mut start_checked,
+                        &mut day_summarised,
+                    )
+                    .await
+                    {
+                        logging::warn!("couldn't send the scheduled notifications: {:#}", e);
+                    }
+                }
+                continue;
+            }
+            _ = &mut scheduled => {
+                scheduled
+                    .as_mut()
+                    .reset(time::Instant::now() + until_scheduled(settings, day_summary_at)?);
+                if !control.is_paused() && !screen_lock.locked {
+                    if let Err(e) = send_scheduled(
+                        config_dir,
+                        settings,
+                        account.as_deref(),
+                        &projects,
+                        &control,
+                        day_summary_at,
+                        &mut start_checked,
+                        &mut day_summarised,
+                    )
+                    .await
+                    {
+                        logging::warn!("couldn't send the scheduled notifications: {:#}", e);
+                    }
+                }
+                continue;
+            }
+            // with nothing changing on disk, what's on screen may be work
+            _ = app_samples.tick(), if track_apps => {
+                let Some(window) = apps::front_window().await else {
+                    continue;
+                };
+                match apps::project_for_window(&projects, &window) {
+                    Some(project) => {
+                        logging::info!(
+                            "{} ({:?}) is in front, counting it as work on {}",
+                            window.app,
+                            window.title,
+                            project.name
+                        );
+                        vec![(project, Source::App, None)]
+                    }
+                    None => continue,
+                }
+            }
+            event = rx.recv() => match event {
+                // a command run in a terminal, in a project or not
+                Some(Ok(ee)) if ee.info() == Some(control::SHELL_ACTIVITY) => {
+                    let Some(path) = ee.paths.into_iter().next() else {
+                        continue;
+                    };
+                    match project_for_file(&projects, &path) {
+                        Some(project) => {
+                            logging::info!("ran a command in {}", project.name);
+                            vec![(project, Source::Shell, Some(path))]
+                        }
+                        None => continue,
+                    }
+                }
+                Some(Ok(ee)) => {
+                    let paths = debounce(&mut rx, ee.paths).await;
+                    app_samples.reset();
+
+                    // project configs created under a search root are picked up
+                    for path in paths.iter() {
+                        if is_project_config_name(path)
+                            && search_roots.iter().any(|root| path.starts_with(root))
+                            && !project_config_paths.contains(path)
+                            && path.exists()
+                        {
+                            logging::info!("Found new project config {}", path.display());
+                            project_config_paths.push(path.clone());
+                        }
+                    }
+
+                    // edits to a project config apply without restarting
+                    if paths.iter().any(|p| {
+                        project_config_paths.contains(p)
+                            || p.file_name()
+                                .and_then(|f| f.to_str())
+                                .is_some_and(|f| IGNORE_FILE_NAMES.contains(&f))
+                    }) {
+                        projects = reload_projects(&project_config_paths, projects);
+                        control.set_projects(&projects);
+                        update_watched_roots(
+                            &mut watcher,
+                            &search_roots,
+                            &project_config_paths,
+                            &mut watched_roots,
+                        );
+                        continue;
+                    }
+
+                    // build output and the like isn't work on the project
+                    let paths: Vec<PathBuf> = paths
+                        .into_iter()
+                        .filter(|path| {
+                            !project_for_file(&projects, path).is_some_and(|p| p.is_ignored(path))
+                        })
+                        .collect();
+                    if paths.is_empty() {
+                        continue;
+                    }
+
+                    logging::info!(
+                        "changed: {:?}, time since {:?}",
+                        paths,
+                        last_request_time.elapsed()
+                    );
+
+                    // what projects were these files in? each counts once,
+                    // however many of its files a save touched
+                    let mut changed: Vec<(&Project, Source, Option<PathBuf>)> = Vec::new();
+                    for path in paths.iter() {
+                        match project_for_file(&projects, path) {
+                            Some(project)
+                                if !changed
+                                    .iter()
+                                    .any(|(c, _, _)| c.config_path == project.config_path) =>
+                            {
+                                changed.push((project, Source::Files, Some(path.clone())))
+                            }
+                            Some(_) => {}
+                            None => logging::info!("{} isn't in any project", path.display()),
+                        }
+                    }
+                    changed
+                }
+                Some(Err(ee)) => {
+                    logging::error!("watch error: {:?}", ee);
+                    continue;
+                }
+                None => return Err(anyhow!("the file watcher stopped")),
+            },
+        };
+
+        for (project, source, path) in changed {
+            // the first change of the day, or after a quiet spell
+            let now = Local::now();
+            let timer =
+                match project_tracker(config_dir, settings, project, account.as_deref()).await {
+                    Ok(tracker) => match tracker.running_time_entry().await {
+                        Ok(running) => TimerState::of(running.as_ref(), project),
+                        Err(_) => TimerState::Unknown,
+                    },
+                    Err(_) => TimerState::Unknown,
+                };
+            control.record_event(project, now, timer);
+            activity::record(
+                config_dir,
+                settings,
+                &Activity::new(project, source, path.as_deref(), timer),
+            );
+            let state = project_state
+                .entry(project.config_path.clone())
+                .or_default();
+            state.idle_checked = false;
+            state.quiet_reminded = false;
+            let starting_work = state.last_activity.replace(now).is_none_or(|last| {
+                settings.date(last) != settings.date(now) || now - last > auto_start_gap
+            });
+
+            // outside working hours activity is only logged
+            if !settings.is_working_time(now)? {
+                logging::info!("outside working hours, not notifying");
+                continue;
+            }
+
+            if control.is_paused() {
+                logging::info!("paused, not notifying");
+                continue;
+            }
+
+            if screen_lock.hold(project) {
+                continue;
+            }
+
+            if project.auto_start && starting_work {
+                last_request_time = Instant::now();
+                let tracker = match project_tracker(
+                    config_dir,
+                    settings,
+                    project,
+                    account.as_deref(),
+                )
+                .await
+                {
+                    Ok(tracker) => tracker,
+                    Err(e) => {
+                        logging::warn!("couldn't check the timer for {}: {:#}", project.name, e);
+                        continue;
+                    }
+                };
+
+                match auto_start_timer(config_dir, settings, project, tracker.as_ref(), &control)
+                    .await
+                {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => {
+                        logging::warn!("couldn't start a timer for {}: {:#}", project.name, e);
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(meeting) = calendar.current(now) {
+                logging::info!(
+                    "in {:?} until {}, not reminding",
+                    meeting.title,
+                    meeting.end.format("%H:%M")
+                );
+                continue;
+            }
+
+            // changes while nobody's at the computer are builds,
+            // syncs and the like rather than work
+            if let Some(idle) = idle::system_idle_time()
+                .await
+                .filter(|idle| *idle >= away_after)
+            {
+                logging::info!(
+                    "no keyboard or mouse use for {} minutes, not reminding",
+                    idle.as_secs() / 60
+                );
+                continue;
+            }
+
+            let mut style =
+                reminder_style(settings, project, reminders.ignored(&project.config_path));
+            let since = match style.interval {
+                Some(_) => project_state
+                    .get(&project.config_path)
+                    .and_then(|state| state.last_reminded)
+                    .map_or(Duration::MAX, |at| at.elapsed()),
+                None => last_request_time.elapsed(),
+            };
+            if since > style.interval.unwrap_or(interval) {
+                project_state
+                    .entry(project.config_path.clone())
+                    .or_default()
+                    .last_reminded = Some(Instant::now());
+                if style.interval.is_none() {
+                    last_request_time = Instant::now();
+                }
+
+                // a reminder still unanswered when the next is due
+                // was ignored, which may call for a louder one
+                let mut remind = true;
+                if reminders.is_pending(&project.config_path) {
+                    let ignored = reminders.ignore(&project.config_path);
+                    style = reminder_style(settings, project, ignored);
+                    if !style.escalated {
+                        logging::info!("still waiting on the last reminder for {}", project.name);
+                        remind = false;
+                    }
+                }
+
+                let tracker = match project_tracker(
+                    config_dir,
+                    settings,
+                    project,
+                    account.as_deref(),
+                )
+                .await
+                {
+                    Ok(tracker) => tracker,
+                    Err(e) => {
+                        logging::warn!("couldn't check the timer for {}: {:#}", project.name, e);
+                        continue;
+                    }
+                };
+
+                if remind {
+                    logging::info!("notifying!");
+                    if let Err(e) = notify_project_timer_status(
+                        config_dir, settings, project, &tracker, &style, &reminders, &control,
+                    )
+                    .await
+                    {
+                        logging::warn!("couldn't remind about {}: {:#}", project.name, e);
+                        continue;
+                    }
+                }
+
+                if let Err(e) = notify_reached_targets(
+                    tracker.as_ref(),
+                    settings,
+                    &projects,
+                    &mut targets_reached,
+                    &control,
+                )
+                .await
+                {
+                    logging::warn!("couldn't check the targets: {:#}", e);
+                }
+            } else {
+                logging::info!("interval hasn't passed, not notifying");
+            }
+        }
+    }
+}
+
+/// what watch keeps track of for each project
+#[derive(Debug, Default)]
+struct ProjectState {
+    /// when it last changed, to tell when work on it starts
+    last_activity: Option<DateTime<Local>>,
+
+    /// when it was last reminded, for projects with an `interval` of their
+    /// own, which are reminded on their own schedule
+    last_reminded: Option<Instant>,
+
+    /// whether its quiet spell has been checked for an idle timer
+    idle_checked: bool,
+
+    /// whether its quiet spell has been asked about
+    quiet_reminded: bool,
+}
+
+/// waits for the rest of a burst of events, as saving a file often creates,
+/// writes and renames several, returning every path they touched once
+async fn debounce(
+    rx: &mut UnboundedReceiver<notify::Result<notify::Event>>,
+    mut paths: Vec<PathBuf>,
+) -> Vec<PathBuf> {
+    let deadline = Instant::now() + DEBOUNCE_LIMIT;
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match time::timeout(DEBOUNCE.min(remaining), rx.recv()).await {
+            Ok(Some(Ok(event))) => paths.extend(event.paths),
+            Ok(Some(Err(e))) => logging::error!("watch error: {:?}", e),
+            // a closed channel is noticed by the next wait in the event loop
+            Ok(None) | Err(_) => break,
+        }
+    }
+
+    dedup_paths(&mut paths);
+    paths
+}
+
+/// sorts paths and drops repeats. windows reports a change to a file a second
+/// time as a change to the directory it's in, and may report the same file
+/// with differently cased paths, so there those go too
+fn dedup_paths(paths: &mut Vec<PathBuf>) {
+    if cfg!(windows) {
+        paths.retain(|path| !path.is_dir());
+        paths.sort_by_cached_key(|path| path.to_string_lossy().to_lowercase());
+        paths.dedup_by(|a, b| {
+            a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+        });
+    } else {
+        paths.sort();
+        paths.dedup();
+    }
+}
+
+/// saves what watch was keeping track of for the next one, along with the
+/// snoozes still to run out, stops listening on the control socket and, with
+/// `notify_on_stop`, says so. the activity log needs nothing more, each line's
+/// written as it's seen
+fn stop(
+    config_dir: &Path,
+    settings: &Settings,
+    control: &Control,
+    reminders: &Reminders,
+    watched: usize,
+) -> anyhow::Result<()> {
+    let mut saved = control.saved_state();
+    for (config_path, project) in saved.projects.iter_mut() {
+        project.ignored_reminders = reminders.ignored(config_path);
+    }
+    let mut state = State::load(config_dir)?;
+    state.watch = saved;
+    let now = chrono::Utc::now();
+    state.snoozed.retain(|_, until| *until > now);
+    state.save(config_dir)?;
+
+    control::unlisten(config_dir);
+
+    if settings.watch.notify_on_stop {
+        notification::show(
+            "Stopped watching",
+            &format!("{} project(s) aren't being watched anymore", watched),
+            &settings.notifications.sound,
+        )?;
+    }
+    Ok(())
+}
+
+/// resolves on ctrl-c, or on SIGTERM from launchd or systemd stopping the
+/// service, with the signal's name
+async fn shutdown_signal() -> anyhow::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            interrupted = tokio::signal::ctrl_c() => Ok(interrupted.map(|_| "SIGINT")?),
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("ctrl-c")
+    }
+}
+
+/// how long until the start reminder or day summary is next due today, or
+/// until midnight, to look again tomorrow
+fn until_scheduled(
+    settings: &Settings,
+    day_summary_at: Option<NaiveTime>,
+) -> anyhow::Result<Duration> {
+    let now = settings.time(Local::now());
+    let until_midnight = chrono::Duration::seconds(86_400 - now.num_seconds_from_midnight() as i64);
+    let next = [
+        start_reminder::reminder_time(settings, settings.today())?,
+        day_summary_at,
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|at| *at > now)
+    .map(|at| at - now)
+    .min()
+    .unwrap_or(until_midnight);
+    Ok(next.to_std().unwrap_or(IDLE_CHECK_INTERVAL))
+}
+
+/// reminds that no timer's been started and sends the day summary once their
+/// times have passed, each once a day
+#[allow(clippy::too_many_arguments)]
+async fn send_scheduled(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    projects: &[Project],
+    control: &Control,
+    day_summary_at: Option<NaiveTime>,
+    start_checked: &mut Option<NaiveDate>,
+    day_summarised: &mut Option<NaiveDate>,
+) -> anyhow::Result<()> {
+    let now = Local::now();
+    let today = settings.date(now);
+    if *start_checked != Some(today) {
+        if let Some(at) = start_reminder::reminder_time(settings, today)? {
+            if settings.time(now) >= at {
+                *start_checked = Some(today);
+                start_reminder::remind_if_not_started(
+                    config_dir, settings, account, projects, control,
+                )
+                .await?;
+            }
+        }
+    }
+    if let Some(at) = day_summary_at {
+        if settings.time(now) >= at && *day_summarised != Some(today) {
+            *day_summarised = Some(today);
+            day_summary::notify_day_summary(config_dir, settings, account, projects, control)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// how long a tracker is reused before its credentials are looked up again,
+/// well within when oauth tokens are refreshed
+const TRACKER_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// a tracker and when its credentials were looked up
+type CachedTracker = (Instant, Arc<dyn TimeTracker>);
+
+/// trackers by account, as finding credentials can ask the keychain, `op` or
+/// `pass` and every change in a project needs one
+static TRACKERS: OnceLock<Mutex<HashMap<Option<String>, CachedTracker>>> = OnceLock::new();
+
+/// the tracker for the account a project is billed to
+async fn project_tracker(
+    config_dir: &Path,
+    settings: &Settings,
+    project: &Project,
+    account: Option<&str>,
+) -> anyhow::Result<Arc<dyn TimeTracker>> {
+    let project_account = project.account.as_deref().or(account);
+    let trackers = TRACKERS.get_or_init(Default::default);
+    let key = project_account.map(str::to_string);
+    if let Some((at, tracker)) = trackers.lock().unwrap().get(&key) {
+        if at.elapsed() < TRACKER_TTL {
+            return Ok(tracker.clone());
+        }
+    }
+
+    let tracker = auth::credentials(config_dir, settings, project_account)
+        .await?
+        .tracker();
+    trackers
+        .lock()
+        .unwrap()
+        .insert(key, (Instant::now(), tracker.clone()));
+    Ok(tracker)
+}
+
+/// starts a timer for a project with `auto_start` when none is running,
+/// returning whether it did
+async fn auto_start_timer(
+    config_dir: &Path,
+    settings: &Settings,
+    project: &Project,
+    tracker: &dyn TimeTracker,
+    control: &Control,
+) -> anyhow::Result<bool> {
+    if tracker.running_time_entry().await?.is_some() {
+        return Ok(false);
+    }
+
+    let task =
+        match timer::start_unattended(config_dir, settings, tracker, project, project.git_branch())
+            .await
+        {
+            Ok(task) => task,
+            Err(e) => {
+                logging::warn!("couldn't start a timer for {}: {:#}", project.name, e);
+                return Ok(false);
+            }
+        };
+    logging::info!("Started timer for {} ({})", project.name, task.task_name);
+    control.reset_untracked(project);
+
+    hooks::run(
+        &settings.hooks,
+        hooks::Event::TimerStarted,
+        project,
+        json!({ "task": task.task_name, "auto_start": true }),
+    );
+
+    let summary = format!("Started timer for {}", project.name_with_branch());
+    notification::show(&summary, &task.task_name, &settings.notifications.sound)?;
+    control.record_notification(&summary, &task.task_name);
+
+    Ok(true)
+}
+
+/// notifies once a day about each daily target that's been reached
+async fn notify_reached_targets(
+    tracker: &dyn TimeTracker,
+    settings: &Settings,
+    projects: &[Project],
+    reached: &mut HashSet<(NaiveDate, Option<i32>)>,
+    control: &Control,
+) -> anyhow::Result<()> {
+    if settings.targets.daily.is_none() && projects.iter().all(|p| p.daily_target.is_none()) {
+        return Ok(());
+    }
+
+    let today = settings.today();
+    let entries = tracker.time_entries(today, today).await?;
+
+    for progress in targets::daily_progress(settings, projects, &entries, "Today") {
+        if !progress.reached() || !reached.insert((today, progress.project_id)) {
+            continue;
+        }
+
+        let summary = match progress.project_id {
+            Some(_) => format!("Target reached for {}", progress.label),
+            None => "Daily target reached".to_string(),
+        };
+        logging::info!("{}", summary);
+        let body = format!(
+            "{} tracked today",
+            decimal_hours_to_string(progress.hours).trim()
+        );
+        notification::show(&summary, &body, &settings.notifications.sound)?;
+        control.record_notification(&summary, &body);
+    }
+
+    Ok(())
+}
+
+/// how often `watch` looks for idle timers
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// how often the front window is checked with `--track-apps`
+const APP_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// quiet time after an event that ends a burst of them
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// longest a burst is waited on, so a build that keeps writing files still
+/// counts as activity
+const DEBOUNCE_LIMIT: Duration = Duration::from_secs(5);
+
+/// stops (or offers to stop) the running timer of a project that's had no
+/// changes for `auto_stop_after`, while nobody's using the computer either
+#[allow(clippy::too_many_arguments)]
+async fn stop_idle_timers(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    projects: &[Project],
+    project_state: &mut HashMap<PathBuf, ProjectState>,
+    auto_stop_after: chrono::Duration,
+    control: &Control,
+) -> anyhow::Result<()> {
+    // someone's at the computer, perhaps working on the project elsewhere
+    if idle::system_idle_time()
+        .await
+        .is_some_and(|idle| idle < auto_stop_after.to_std().unwrap_or_default())
+    {
+        return Ok(());
+    }
+
+    let now = Local::now();
+    for project in projects {
+        // projects without changes since watching started are left alone,
+        // there's nothing to say when work on them stopped
+        let state = project_state
+            .entry(project.config_path.clone())
+            .or_default();
+        let Some(last) = state.last_activity else {
+            continue;
+        };
+        if now - last < auto_stop_after || state.idle_checked {
+            continue;
+        }
+        state.idle_checked = true;
+
+        let tracker = project_tracker(config_dir, settings, project, account).await?;
+        let Some(running) = tracker.running_time_entry().await? else {
+            continue;
+        };
+        if running.project.id != project.harvest_project_id {
+            continue;
+        }
+
+        let ended_at = settings.watch.auto_stop_backdate.then_some(last);
+        let minutes = (now - last).num_minutes();
+
+        if settings.watch.auto_stop_prompt {
+            prompt_to_stop_idle_timer(
+                tracker,
+                settings.clone(),
+                project.clone(),
+                running,
+                ended_at,
+                format!("Timer idle for {}", project.name),
+                format!("No changes for {} minutes", minutes),
+            );
+            continue;
+        }
+
+        let stopped = timer::stop_entry(tracker.as_ref(), settings, &running, ended_at).await?;
+        logging::info!("{}", stopped);
+        hooks::run(
+            &settings.hooks,
+            hooks::Event::TimerStopped,
+            project,
+            json!({ "entry_id": running.id, "idle_minutes": minutes }),
+        );
+        let summary = format!("Stopped idle timer for {}", project.name);
+        let body = format!("No changes for {} minutes", minutes);
+        notification::show(&summary, &body, &settings.notifications.sound)?;
+        control.record_notification(&summary, &body);
+    }
+
+    Ok(())
+}
+
+/// reminds, with a button to stop it, about a timer whose project has had no
+/// changes for `quiet_after`, once each quiet spell, whether or not someone's
+/// at the computer
+#[allow(clippy::too_many_arguments)]
+async fn remind_quiet_timers(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    projects: &[Project],
+    project_state: &mut HashMap<PathBuf, ProjectState>,
+    watching_since: DateTime<Local>,
+    quiet_after: chrono::Duration,
+    control: &Control,
+) -> anyhow::Result<()> {
+    let now = Local::now();
+    if !settings.is_working_time(now)? {
+        return Ok(());
+    }
+
+    for project in projects {
+        // without changes since watching started, it's been quiet since then
+        let state = project_state
+            .entry(project.config_path.clone())
+            .or_default();
+        let last = state.last_activity.unwrap_or(watching_since);
+        if now - last < quiet_after || state.quiet_reminded {
+            continue;
+        }
+
+        let tracker = project_tracker(config_dir, settings, project, account).await?;
+        let Some(running) = tracker.running_time_entry().await? else {
+            continue;
+        };
+        if running.project.id != project.harvest_project_id {
+            continue;
+        }
+
+        // a timer started during the quiet spell has been quiet since then
+        let quiet_since = running
+            .timer_started_at
+            .map_or(last, |at| last.max(at.with_timezone(&Local)));
+        let minutes = (now - quiet_since).num_minutes();
+        if now - quiet_since < quiet_after {
+            continue;
+        }
+        state.quiet_reminded = true;
+
+        let summary = format!("Still working on {}?", project.name);
+        let body = format!("Timer has been running {} min without activity", minutes);
+        logging::info!("{}: {}", summary, body);
+        control.record_notification(&summary, &body);
+        prompt_to_stop_idle_timer(
+            tracker,
+            settings.clone(),
+            project.clone(),
+            running,
+            settings.watch.auto_stop_backdate.then_some(quiet_since),
+            summary,
+            body,
+        );
+    }
+
+    Ok(())
+}
+
+/// asks with a notification whether to stop an idle timer, in the background
+fn prompt_to_stop_idle_timer(
+    tracker: Arc<dyn TimeTracker>,
+    settings: Settings,
+    project: Project,
+    running: TimeEntry,
+    ended_at: Option<DateTime<Local>>,
+    summary: String,
+    body: String,
+) {
+    tokio::spawn(async move {
+        let sound = settings.notifications.sound.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            notification::ask(
+                &summary,
+                &body,
+                "Stop",
+                "Keep running",
+                &sound,
+                Urgency::Normal,
+            )
+        })
+        .await;
+
+        match response {
+            Ok(Ok(Answer::Action)) => {
+                match timer::stop_entry(tracker.as_ref(), &settings, &running, ended_at).await {
+                    Ok(stopped) => {
+                        logging::info!("{}", stopped);
+                        hooks::run(
+                            &settings.hooks,
+                            hooks::Event::TimerStopped,
+                            &project,
+                            json!({ "entry_id": running.id }),
+                        );
+                    }
+                    Err(e) => {
+                        logging::warn!("couldn't stop the timer for {}: {:#}", project.name, e)
+                    }
+                }
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => logging::warn!("notification error: {:?}", e),
+            Err(e) => logging::warn!("notification error: {:?}", e),
+        }
+    });
+}
+
+/// warns about projects inside other projects, whose files count towards
+/// the innermost one only
+fn warn_about_overlapping_roots(projects: &[Project]) {
+    for (i, outer) in projects.iter().enumerate() {
+        for (j, inner) in projects
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| outer.overlaps(p))
+        {
+            if inner.root != outer.root {
+                logging::warn!(
+                    "{} ({}) is inside {} ({}), changes there count for {} only",
+                    inner.name,
+                    inner.root.display(),
+                    outer.name,
+                    outer.root.display(),
+                    inner.name
+                );
+            } else if i < j {
+                // the last one listed wins a tie
+                logging::warn!(
+                    "{} and {} share the root {}, changes there count for {} only",
+                    outer.name,
+                    inner.name,
+                    inner.root.display(),
+                    inner.name
+                );
+            }
+        }
+    }
+}
+
+/// re-reads every project config, keeping the previous version of a project
+/// whose config is temporarily unreadable (e.g. half way through a save)
+fn reload_projects(project_config_paths: &[PathBuf], previous: Vec<Project>) -> Vec<Project> {
+    let mut projects = Vec::new();
+
+    for path in project_config_paths.iter() {
+        let previous_project = previous.iter().find(|p| &p.config_path == path);
+
+        match read_project_config(path) {
+            Ok(project) => {
+                match previous_project {
+                    Some(previous_project)
+                        if previous_project.name == project.name
+                            && previous_project.harvest_project_id
+                                == project.harvest_project_id
+                            && previous_project.account == project.account => {}
+                    _ => logging::info!("Reloaded {} from {}", project.name, path.display()),
+                }
+                projects.push(project);
+            }
+            Err(_) if !path.exists() => {
+                if let Some(previous_project) = previous_project {
+                    logging::info!(
+                        "{} was removed, no longer tracking {}",
+                        path.display(),
+                        previous_project.name
+                    );
+                }
+            }
+            Err(e) => {
+                logging::warn!("couldn't reload {}: {:#}", path.display(), e);
+                if let Some(previous_project) = previous_project {
+                    projects.push(previous_project.clone());
+                }
+            }
+        }
+    }
+
+    projects
+}
+
+/// watches the search roots plus the root of every project config outside
+/// them whose directory exists, so a config that's removed and recreated is
+/// noticed too
+fn update_watched_roots(
+    watcher: &mut RecommendedWatcher,
+    search_roots: &[PathBuf],
+    project_config_paths: &[PathBuf],
+    watched_roots: &mut HashSet<PathBuf>,
+) {
+    let roots: HashSet<PathBuf> = project_config_paths
+        .iter()
+        .filter_map(|path| project_root(path).ok())
+        .filter(|root| root.is_dir())
+        .filter(|root| !search_roots.iter().any(|s| root.starts_with(s)))
+        .chain(search_roots.iter().cloned())
+        .collect();
+
+    for root in watched_roots.difference(&roots) {
+        // the directory is gone, so the watch usually already is too
+        let _ = watcher.unwatch(root);
+        logging::info!("Stopped watching {:?}", root);
+    }
+
+    for root in roots.difference(watched_roots) {
+        match watcher.watch(root, RecursiveMode::Recursive) {
+            Ok(()) => logging::info!("Watching {:?}", root),
+            Err(e) => logging::warn!("couldn't watch {:?}: {:?}", root, e),
+        }
+    }
+
+    *watched_roots = roots;
+}
+
+// an enum with three possible states: true, timer not running, timer for different project
+enum TimerStatus {
+    Running,
+    NotRunning,
+    ForDifferentProject(Box<TimeEntry>),
+}
+
+async fn is_active_timer_for_project(
+    tracker: &dyn TimeTracker,
+    project: &Project,
+) -> anyhow::Result<TimerStatus> {
+    let running_timer = tracker.running_time_entry().await?;
+
+    match running_timer {
+        Some(timer) => {
+            if timer.project.id == project.harvest_project_id {
+                Ok(TimerStatus::Running)
+            } else {
+                Ok(TimerStatus::ForDifferentProject(Box::new(timer)))
+            }
+        }
+        None => Ok(TimerStatus::NotRunning),
+    }
+}
+
+/// asks in the background whether to log a meeting that's just ended, with
+/// `log_alias` from [calendar] and the meeting's title as notes
+fn offer_to_log_meeting(
+    config_dir: &Path,
+    settings: &Settings,
+    account: Option<&str>,
+    alias: &str,
+    meeting: Meeting,
+    control: &Control,
+) {
+    let summary = format!("Log {}?", meeting.title);
+    let body = format!(
+        "{} to {}",
+        decimal_hours_to_string(meeting.hours()).trim(),
+        alias
+    );
+    logging::info!("{}", summary);
+    control.record_notification(&summary, &body);
+
+    let (config_dir, settings, account, alias) = (
+        config_dir.to_path_buf(),
+        settings.clone(),
+        account.map(|a| a.to_string()),
+        alias.to_string(),
+    );
+    tokio::spawn(async move {
+        let sound = settings.notifications.sound.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            notification::ask(&summary, &body, "Log", "Skip", &sound, Urgency::Normal)
+        })
+        .await;
+        if !matches!(response, Ok(Ok(Answer::Action))) {
+            return;
+        }
+
+        let logged = async {
+            let target = timer::EntryTarget {
+                hours: Some(meeting.hours()),
+                notes: Some(meeting.title.clone()),
+                ..Default::default()
+            }
+            .or_alias(settings.alias(&alias)?)?;
+            timer::log(
+                &config_dir,
+                &settings,
+                account.as_deref(),
+                target,
+                Some(meeting.start.date_naive()),
+            )
+            .await
+        };
+        if let Err(e) = logged.await {
+            logging::warn!("couldn't log {}: {:#}", meeting.title, e);
+        }
+    });
+}
+
+/// how often the screen is checked for being locked
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// whether the screen's locked, and the reminder held back while it was
+struct ScreenLock {
+    locked: bool,
+    checked: Instant,
+    checked_at: DateTime<Local>,
+    held: Option<PathBuf>,
+}
+
+impl ScreenLock {
+    async fn new() -> Self {
+        ScreenLock {
+            locked: idle::screen_locked().await,
+            checked: Instant::now(),
+            checked_at: Local::now(),
+            held: None,
+        }
+    }
+
+    /// looks at the screen again, returning the held reminder's project once
+    /// it's unlocked or the computer's woken up. sleep shows as the clock
+    /// moving on further than `Instant`, which stops while asleep
+    async fn check(&mut self) -> Option<PathBuf> {
+        let slept = (Local::now() - self.checked_at)
+            .to_std()
+            .is_ok_and(|elapsed| elapsed > self.checked.elapsed() + Duration::from_secs(60));
+        let was_locked = self.locked;
+        self.locked = idle::screen_locked().await;
+        self.checked = Instant::now();
+        self.checked_at = Local::now();
+
+        if slept {
+            logging::info!("woke from sleep");
+        }
+        if was_locked && !self.locked {
+            logging::info!("screen unlocked");
+        }
+        if (slept || was_locked) && !self.locked {
+            self.held.take()
+        } else {
+            None
+        }
+    }
+
+    /// holds back a reminder for a project while the screen's locked, only
+    /// the latest is sent once it's unlocked
+    fn hold(&mut self, project: &Project) -> bool {
+        if self.locked {
+            logging::info!(
+                "screen is locked, holding the reminder for {}",
+                project.name
+            );
+            self.held = Some(project.config_path.clone());
+        }
+        self.locked
+    }
+}
+
+/// how long the snooze button holds off reminders for a project
+const SNOOZE: Duration = Duration::from_secs(30 * 60);
+
+/// reminders waiting on a button press, and how many in a row have been
+/// ignored, shared with the tasks waiting on notifications
+#[derive(Default, Clone)]
+struct Reminders(Arc<Mutex<ReminderState>>);
+
+#[derive(Default)]
+struct ReminderState {
+    pending: HashMap<PathBuf, usize>,
+    ignored: HashMap<PathBuf, usize>,
+}
+
+impl Reminders {
+    fn is_pending(&self, config_path: &Path) -> bool {
+        self.0.lock().unwrap().pending.contains_key(config_path)
+    }
+
+    fn begin(&self, config_path: &Path) {
+        *self
+            .0
+            .lock()
+            .unwrap()
+            .pending
+            .entry(config_path.to_path_buf())
+            .or_default() += 1;
+    }
+
+    fn end(&self, config_path: &Path) {
+        let mut state = self.0.lock().unwrap();
+        if let Some(count) = state.pending.get_mut(config_path) {
+            *count -= 1;
+            if *count == 0 {
+                state.pending.remove(config_path);
+            }
+        }
+    }
+
+    fn ignored(&self, config_path: &Path) -> usize {
+        self.0
+            .lock()
+            .unwrap()
+            .ignored
+            .get(config_path)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// counts another ignored reminder, returning how many there have been
+    fn ignore(&self, config_path: &Path) -> usize {
+        let mut state = self.0.lock().unwrap();
+        let ignored = state.ignored.entry(config_path.to_path_buf()).or_default();
+        *ignored += 1;
+        *ignored
+    }
+
+    /// carries on with the count of ignored reminders from a watch that was
+    /// stopped
+    fn restore(&self, config_path: &Path, ignored: usize) {
+        if ignored > 0 {
+            self.0
+                .lock()
+                .unwrap()
+                .ignored
+                .insert(config_path.to_path_buf(), ignored);
+        }
+    }
+
+    /// back to the gentlest reminder, once one's answered or a timer's running
+    fn reset(&self, config_path: &Path) {
+        self.0.lock().unwrap().ignored.remove(config_path);
+    }
+}
+
+/// how a project's next reminder is given, after its overrides and the
+/// escalation for the reminders ignored so far
+struct ReminderStyle {
+    /// seconds until the next reminder, when it isn't the global interval
+    interval: Option<Duration>,
+    sound: String,
+    urgency: Urgency,
+    dialog: bool,
+
+    /// whether an escalation step applies
+    escalated: bool,
+}
+
+fn reminder_style(settings: &Settings, project: &Project, ignored: usize) -> ReminderStyle {
+    let steps = if project.escalation.is_empty() {
+        &settings.watch.escalation
+    } else {
+        &project.escalation
+    };
+    // the last step stays once they run out
+    let step = ignored
+        .checked_sub(1)
+        .and_then(|i| steps.get(i.min(steps.len().saturating_sub(1))));
+
+    ReminderStyle {
+        interval: step
+            .and_then(|s| s.interval)
+            .or(project.interval)
+            .map(Duration::from_secs),
+        sound: step
+            .and_then(|s| s.sound.clone())
+            .or_else(|| project.sound.clone())
+            .unwrap_or_else(|| settings.notifications.sound.clone()),
+        urgency: step
+            .and_then(|s| s.urgency)
+            .or(project.urgency)
+            .unwrap_or_default(),
+        dialog: step.is_some_and(|s| s.dialog),
+        escalated: step.is_some(),
+    }
+}
+
+async fn notify_project_timer_status(
+    config_dir: &Path,
+    settings: &Settings,
+    project: &Project,
+    tracker: &Arc<dyn TimeTracker>,
+    style: &ReminderStyle,
+    reminders: &Reminders,
+    control: &Control,
+) -> anyhow::Result<()> {
+    // snoozed here or with `clockhand snooze`
+    if let Some(until) = State::load(config_dir)?.snoozed_until(project.harvest_project_id) {
+        logging::info!(
+            "reminders for {} are snoozed until {}",
+            project.name,
+            until.with_timezone(&Local).format("%H:%M")
+        );
+        return Ok(());
+    }
+
+    // use the harvest api to determine if there's an active timer running
+    let active_timer = is_active_timer_for_project(tracker.as_ref(), project).await?;
+
+    let (status, other_timer) = match &active_timer {
+        TimerStatus::Running => {
+            reminders.reset(&project.config_path);
+            control.reset_untracked(project);
+            return Ok(());
+        }
+        TimerStatus::NotRunning => ("Timer not running", None),
+        TimerStatus::ForDifferentProject(timer) => ("Timer running for other project", Some(timer)),
+    };
+
+    // the branch is the notes of a timer started from the reminder
+    let branch = project.git_branch();
+    let untracked_minutes = control.untracked_minutes(project);
+    let templates = project
+        .templates
+        .or(&settings.notifications.templates)
+        .or(&template::default_templates());
+    let variables = [
+        ("project", project.name.clone()),
+        ("branch", branch.clone().unwrap_or_default()),
+        ("project_and_branch", project.name_with_branch()),
+        ("status", status.to_string()),
+        ("untracked_minutes", untracked_minutes.to_string()),
+        (
+            "timer_project",
+            other_timer
+                .map(|t| t.project.name.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "timer_duration",
+            other_timer
+                .map(|t| {
+                    decimal_hours_to_string(t.current_hours())
+                        .trim()
+                        .to_string()
+                })
+                .unwrap_or_default(),
+        ),
+    ];
+    let summary = template::render(templates.summary.as_deref().unwrap_or_default(), &variables);
+    let body = match untracked_minutes {
+        0 => templates.body,
+        _ => templates.untracked_body,
+    };
+    let body = template::render(body.as_deref().unwrap_or_default(), &variables);
+    let untracked = json!({
+        "untracked_minutes": untracked_minutes,
+        "other_timer_running": other_timer.is_some(),
+    });
+    hooks::run(
+        &settings.hooks,
+        hooks::Event::UntrackedActivity,
+        project,
+        untracked,
+    );
+
+    reminders.begin(&project.config_path);
+    control.record_notification(&summary, &body);
+    hooks::run(
+        &settings.hooks,
+        hooks::Event::ReminderSent,
+        project,
+        json!({ "summary": summary, "body": body }),
+    );
+
+    // notifications with buttons block until they're answered, so they're
+    // waited on in the background while watching carries on
+    let (config_dir, settings, project, tracker, sound, urgency, dialog, reminders, control) = (
+        config_dir.to_path_buf(),
+        settings.clone(),
+        project.clone(),
+        tracker.clone(),
+        style.sound.clone(),
+        style.urgency,
+        style.dialog,
+        reminders.clone(),
+        control.clone(),
+    );
+    tokio::spawn(async move {
+        let response = tokio::task::spawn_blocking(move || {
+            if dialog {
+                notification::dialog(&summary, &body, "Start", "Snooze 30m", &sound)
+            } else {
+                notification::ask(&summary, &body, "Start", "Snooze 30m", &sound, urgency)
+            }
+        })
+        .await;
+
+        reminders.end(&project.config_path);
+        match response {
+            Ok(Ok(Answer::Dismissed)) => {
+                reminders.ignore(&project.config_path);
+            }
+            Ok(Ok(_)) => reminders.reset(&project.config_path),
+            _ => {}
+        }
+
+        match response {
+            Ok(Ok(Answer::Action)) => {
+                match timer::start_unattended(
+                    &config_dir,
+                    &settings,
+                    tracker.as_ref(),
+                    &project,
+                    branch,
+                )
+                .await
+                {
+                    Ok(task) => {
+                        control.reset_untracked(&project);
+                        hooks::run(
+                            &settings.hooks,
+                            hooks::Event::TimerStarted,
+                            &project,
+                            json!({ "task": task.task_name, "auto_start": false }),
+                        );
+                        logging::info!("Started timer for {} ({})", project.name, task.task_name)
+                    }
+                    Err(e) => {
+                        logging::warn!("couldn't start a timer for {}: {:#}", project.name, e)
+                    }
+                }
+            }
+            Ok(Ok(Answer::Close)) => match snooze::snooze_project(&config_dir, &project, SNOOZE) {
+                Ok(_) => logging::info!("Snoozed reminders for {}", project.name),
+                Err(e) => logging::warn!("couldn't snooze {}: {:#}", project.name, e),
+            },
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => logging::warn!("notification error: {:?}", e),
+            Err(e) => logging::warn!("notification error: {:?}", e),
+        }
+    });
+
+    Ok(())
+}